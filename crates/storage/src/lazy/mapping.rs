@@ -88,6 +88,35 @@ where
         pull_packed_root_opt(&self.storage_key(key))
     }
 
+    // NOTE: `contains` and `remove` are a local patch of the naming service contract, they
+    // are not part of upstream ink! 3.0.0-rc7. Clearing a cell needs the private
+    // `storage_key`, so removal cannot live in the contract. See the contract README.
+
+    /// Returns `true` if a `value` is stored at `key` in the contract storage.
+    #[inline]
+    pub fn contains<Q>(&self, key: Q) -> bool
+    where
+        Q: scale::EncodeLike<K>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Clears the `value` at `key` from the contract storage.
+    #[inline]
+    pub fn remove<Q>(&self, key: Q)
+    where
+        Q: scale::EncodeLike<K>,
+    {
+        let storage_key = self.storage_key(key);
+        if <V as SpreadLayout>::REQUIRES_DEEP_CLEAN_UP {
+            // Values which own storage cells of their own have to clean those up first.
+            if let Some(value) = pull_packed_root_opt::<V>(&storage_key) {
+                <V as PackedLayout>::clear_packed(&value, &storage_key);
+            }
+        }
+        ink_env::clear_contract_storage(&storage_key);
+    }
+
     /// Returns a `Key` pointer used internally by the storage API.
     ///
     /// This key is a combination of the `Mapping`'s internal `offset_key`
@@ -176,6 +205,22 @@ mod tests {
         .unwrap()
     }
 
+    #[test]
+    fn contains_and_remove_work() {
+        ink_env::test::run_test::<ink_env::DefaultEnvironment, _>(|_| {
+            let mut mapping: Mapping<u8, u8> = Mapping::new([0u8; 32].into());
+            mapping.insert(&1, &2);
+            assert!(mapping.contains(&1));
+            assert!(!mapping.contains(&2));
+            mapping.remove(&1);
+            assert!(!mapping.contains(&1));
+            assert_eq!(mapping.get(&1), None);
+
+            Ok(())
+        })
+        .unwrap()
+    }
+
     #[test]
     fn gets_default_if_no_key_set() {
         ink_env::test::run_test::<ink_env::DefaultEnvironment, _>(|_| {
@@ -1,14 +1,25 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 use ink_lang as ink;
 
+/// Interface of resolver contracts that resolve a node to an account.
+///
+/// `PeerName::resolve_addr` calls the `addr` message of the stored resolver.
+#[ink::trait_definition]
+pub trait AddrResolver {
+    /// Account the node resolves to, if any
+    #[ink(message)]
+    fn addr(&self, node: [u8; 32]) -> Option<ink_env::AccountId>;
+}
+
 #[ink::contract]
 mod Decentralised_Naming_Service {
 
     use ink_prelude::vec::Vec;
 
-    use ink_storage::collections::HashMap;
+    use ink_storage::{lazy::Mapping, traits::SpreadAllocate};
 
-    use ink_env::hash::{Blake2x128, HashOutput};
+    use ink_env::call::{build_call, utils::ReturnType, ExecutionInput, Selector};
+    use ink_env::hash::{Blake2x256, HashOutput};
     //use ink_storage:: collections:: Vec;
     use scale::{Decode, Encode};
 
@@ -16,6 +27,62 @@ mod Decentralised_Naming_Service {
     // It could be a wallet, contract, IPFS content hash, IPv4, IPv6 etc
     pub type Resolver = AccountId;
 
+    // A registered node with its owner and resolver, see `PeerName::export_nodes`.
+    pub type NodeExport = ([u8; 32], AccountId, Option<Resolver>);
+
+    // Coin type reserved for the native `Resolver` of a name.
+    // Addresses of other chains use their SLIP-44 coin type.
+    pub const NATIVE_COIN_TYPE: u32 = u32::MAX;
+
+    // Kinds of value a resolver stands for, see `PeerName::set_record_kind`.
+    pub const RECORD_KIND_WALLET: u8 = 0;
+    pub const RECORD_KIND_CONTRACT: u8 = 1;
+    pub const RECORD_KIND_IPFS: u8 = 2;
+    pub const RECORD_KIND_IPV4: u8 = 3;
+    pub const RECORD_KIND_IPV6: u8 = 4;
+
+    // Permissions an owner can give up for good, see `PeerName::burn_fuses`.
+    pub const FUSE_CANNOT_TRANSFER: u32 = 1;
+    pub const FUSE_CANNOT_SET_RESOLVER: u32 = 2;
+    pub const FUSE_CANNOT_CREATE_SUBDOMAIN: u32 = 4;
+
+    // Maximum number of names handled by a single batch call.
+    pub const MAX_BATCH_SIZE: usize = 50;
+
+    // Maximum number of text records set along with a registration.
+    pub const MAX_INITIAL_TEXTS: usize = 10;
+
+    // Resolver changes kept per name by `PeerName::resolver_history`.
+    pub const MAX_RESOLVER_HISTORY: usize = 10;
+
+    // Registration prices are charged per started period of this many seconds.
+    pub const PRICE_PERIOD_SECS: u64 = 365 * 24 * 60 * 60;
+
+    // Maximum number of nodes returned by a single page query.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    // Configuration of contracts deployed through the `new` constructor.
+    pub const DEFAULT_GRACE_PERIOD_SECS: u64 = 90 * 24 * 60 * 60;
+    pub const DEFAULT_MIN_LABEL_LEN: u32 = 1;
+    pub const DEFAULT_MAX_LABEL_LEN: u32 = 63;
+    pub const DEFAULT_MIN_COMMIT_AGE_SECS: u64 = 60;
+    pub const DEFAULT_MAX_COMMIT_AGE_SECS: u64 = 24 * 60 * 60;
+
+    // Layout version written by this code, see `PeerName::migrate`.
+    pub const STORAGE_VERSION: u16 = 1;
+
+    // Leading bytes of the node and subnode hash inputs, keep the two hash spaces apart.
+    pub const NODE_TAG: u8 = 0;
+    pub const SUBNODE_TAG: u8 = 1;
+
+    // Nodes and commitments are returned straight from the hash output buffer,
+    // fail the build if an ink update changes its size.
+    const _: () = assert!(core::mem::size_of::<<Blake2x256 as HashOutput>::Type>() == 32);
+
+    // Whether a transfer restarts the registration age of a name.
+    // The age then tells how long the current owner has held the name.
+    pub const RESET_REGISTERED_AT_ON_TRANSFER: bool = true;
+
     #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub enum Error {
@@ -34,40 +101,137 @@ mod Decentralised_Naming_Service {
         NameNotExists,
         /// Returned if caller is not owner while required to.
         CallerIsNotOwner,
+        /// Returned if the name registration has expired.
+        DomainExpired,
+        /// Returned if the name has expired but is still reserved for its owner.
+        InGracePeriod,
+        /// Returned if the transferred value does not cover the registration fee.
+        InsufficientFee,
+        /// Returned if an address record has the wrong format for its coin type.
+        InvalidAddress,
+        /// Returned if a label is too short, too long or contains disallowed characters.
+        InvalidName,
+        /// Returned if no live commitment matches the registration.
+        CommitmentNotFound,
+        /// Returned if the commitment has not waited the minimum commit age yet.
+        CommitmentTooNew,
+        /// Returned if the name is reserved and cannot be registered.
+        NameReserved,
+        /// Returned if a batch call exceeds `MAX_BATCH_SIZE` entries.
+        BatchTooLarge,
+        /// Returned if state changing operations are paused.
+        Paused,
+        /// Returned if a transfer out of the contract failed.
+        TransferFailed,
+        /// Returned if the contract balance is lower than the requested amount.
+        InsufficientBalance,
+        /// Returned if the name is locked against transfers.
+        DomainLocked,
+        /// Returned if a name would be transferred to its current owner or the zero account.
+        InvalidOwner,
+        /// Returned if a rate in basis points exceeds 10000.
+        InvalidRate,
+        /// Returned if a timestamp or amount calculation overflows.
+        ArithmeticOverflow,
+        /// Returned if the resolver of a name is frozen by a staged transfer.
+        TransferPending,
+        /// Returned if a name has no staged transfer to cancel.
+        NoPendingTransfer,
+        /// Returned if the parent already has `max_subdomains` subdomains.
+        SubdomainLimitReached,
+        /// Returned if the owner of the parent froze its set of subdomains.
+        SubdomainsFrozen,
+        /// Returned if the manager exceeds `max_registrations_per_window`.
+        RateLimited,
+        /// Returned if a signature was not made by the expected account.
+        InvalidSignature,
+        /// Returned if a signed nonce is not the next nonce of the signer.
+        BadNonce,
+        /// Returned if the name already has a resolver.
+        ResolverAlreadySet,
+        /// Returned if a registration or renewal is shorter than `min_duration_secs`.
+        DurationTooShort,
+        /// Returned if a registration, or the term left after a renewal, is longer than
+        /// `max_duration_secs`.
+        DurationTooLong,
+        /// Returned if the resolver allowlist is enabled and does not list the resolver.
+        ResolverNotAllowed,
+        /// Returned if a record kind is not one of the `RECORD_KIND_*` constants.
+        InvalidRecordKind,
+        /// Returned if a burned fuse rules out the action.
+        FuseBurned,
     }
 
+    /// Everything known about a registered name, see `PeerName::domain_info`.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct DomainInfo {
+        pub owner: AccountId,
+        pub resolver: Option<Resolver>,
+        pub expiry: Option<Timestamp>,
+        pub label: Option<Vec<u8>>,
+        pub locked: bool,
+    }
+
+    /// Which kinds of records are set on a name, see `PeerName::records_present`.
+    #[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Copy, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct RecordFlags {
+        pub has_resolver: bool,
+        pub has_content_hash: bool,
+        pub has_any_text: bool,
+        pub has_any_addr: bool,
+    }
+
+    /// Current and proposed admin and manager, see `PeerName::governance`.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Copy, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Governance {
+        pub admin: AccountId,
+        pub manager: AccountId,
+        pub pending_admin: Option<AccountId>,
+        pub pending_manager: Option<AccountId>,
+    }
+
+    /// Emitted when a node gets its first owner, changes of owner emit `Transfer` instead.
     #[ink(event)]
     pub struct NewOwner {
         #[ink(topic)]
-        node: [u8; 16],
+        node: [u8; 32],
         #[ink(topic)]
         owner: AccountId,
     }
 
+    /// Emitted when a subdomain is created, links the parent node to the subnode.
     #[ink(event)]
     pub struct SubNode {
         #[ink(topic)]
         owner: AccountId,
         #[ink(topic)]
-        node: [u8; 16],
+        node: [u8; 32],
         #[ink(topic)]
-        subnode: [u8; 16],
+        subnode: [u8; 32],
     }
 
+    /// Emitted whenever the resolver of a node is set, with the resolver it replaced.
     #[ink(event)]
     pub struct NewResolver {
         #[ink(topic)]
-        node: [u8; 16],
+        node: [u8; 32],
         #[ink(topic)]
         resolver: Resolver,
+        old_resolver: Option<Resolver>,
     }
 
+    /// Emitted only when an existing name changes hands through a transfer.
     #[ink(event)]
     pub struct Transfer {
         #[ink(topic)]
-        node: [u8; 16],
+        node: [u8; 32],
         #[ink(topic)]
         owner: AccountId,
+        /// Readable name of the node
+        label: Vec<u8>,
     }
 
     /// Event emitted when admin change manager.
@@ -79,20 +243,27 @@ mod Decentralised_Naming_Service {
         _new_manager: Option<AccountId>,
     }
 
-    /// Emitted whenever a new name is being registered.
+    /// Emitted whenever a new name or subdomain is being registered.
     #[ink(event)]
     pub struct Register {
         #[ink(topic)]
-        node: [u8; 16],
+        node: [u8; 32],
         #[ink(topic)]
         from: AccountId,
+        /// Account that sent the registration, e.g. the manager
+        #[ink(topic)]
+        registrar: AccountId,
+        /// Readable name of the node, `subdomain.domain` for subnodes
+        label: Vec<u8>,
+        /// Resolver set by the registration, `None` for a subdomain without one
+        resolver: Option<Resolver>,
     }
 
     /// Emitted whenever an address changes.
     #[ink(event)]
     pub struct SetAddress {
         #[ink(topic)]
-        name: [u8; 16],
+        name: [u8; 32],
         from: AccountId,
         #[ink(topic)]
         old_address: Option<AccountId>,
@@ -100,305 +271,6139 @@ mod Decentralised_Naming_Service {
         new_address: AccountId,
     }
 
+    /// Emitted whenever a name registration is renewed.
+    #[ink(event)]
+    pub struct Renew {
+        #[ink(topic)]
+        node: [u8; 32],
+        new_expiry: Timestamp,
+    }
+
+    /// Emitted whenever the admin withdraws collected fees.
+    #[ink(event)]
+    pub struct Withdrawal {
+        #[ink(topic)]
+        to: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted whenever a text record of a name changes.
+    #[ink(event)]
+    pub struct TextChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        key: Vec<u8>,
+    }
+
+    /// Emitted whenever an address record of a name changes.
+    #[ink(event)]
+    pub struct AddrChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        #[ink(topic)]
+        coin_type: u32,
+    }
+
+    /// Emitted whenever the content hash of a name changes.
+    #[ink(event)]
+    pub struct ContentHashChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+    }
+
+    /// Emitted whenever the IPv4 address of a name changes.
+    #[ink(event)]
+    pub struct Ipv4Changed {
+        #[ink(topic)]
+        node: [u8; 32],
+        addr: [u8; 4],
+    }
+
+    /// Emitted whenever the IPv6 address of a name changes.
+    #[ink(event)]
+    pub struct Ipv6Changed {
+        #[ink(topic)]
+        node: [u8; 32],
+        addr: [u8; 16],
+    }
+
+    /// Emitted when the owner of a name freezes its set of subdomains.
+    #[ink(event)]
+    pub struct SubdomainsFrozen {
+        #[ink(topic)]
+        node: [u8; 32],
+    }
+
+    /// Emitted when the owner of a name burns fuses.
+    #[ink(event)]
+    pub struct FusesBurned {
+        #[ink(topic)]
+        node: [u8; 32],
+        fuses: u32,
+    }
+
+    /// Emitted whenever the record kind of a name changes.
+    #[ink(event)]
+    pub struct RecordKindChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        kind: u8,
+    }
+
+    /// Emitted whenever an operator is enabled or disabled for an owner.
+    #[ink(event)]
+    pub struct ApprovalForAll {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        approved: bool,
+    }
+
+    /// Emitted when a new admin accepts the role, or with the zero account on `renounce_admin`.
+    #[ink(event)]
+    pub struct AdminTransferred {
+        #[ink(topic)]
+        old: AccountId,
+        #[ink(topic)]
+        new: AccountId,
+    }
+
+    /// Emitted whenever the ownership of a name is renounced.
+    #[ink(event)]
+    pub struct Renounce {
+        #[ink(topic)]
+        node: [u8; 32],
+        #[ink(topic)]
+        previous_owner: AccountId,
+    }
+
+    /// Emitted when the admin pauses state changing operations.
+    #[ink(event)]
+    pub struct Paused {
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    /// Emitted when the admin resumes state changing operations.
+    #[ink(event)]
+    pub struct Unpaused {
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    /// Emitted whenever the TTL of a name changes.
+    #[ink(event)]
+    pub struct TtlChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        ttl: u32,
+    }
+
+    /// Emitted whenever the transfer lock of a name is set or released.
+    #[ink(event)]
+    pub struct LockChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        locked: bool,
+    }
+
+    /// Emitted whenever the controller of a name changes.
+    #[ink(event)]
+    pub struct ControllerChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        controller: Option<AccountId>,
+    }
+
+    /// Emitted when a transfer is staged, or with `None` once the owner cancels it.
+    #[ink(event)]
+    pub struct PendingTransferChanged {
+        #[ink(topic)]
+        node: [u8; 32],
+        recipient: Option<AccountId>,
+    }
+
+    /// Emitted whenever a referrer is credited for a registration.
+    #[ink(event)]
+    pub struct ReferralPaid {
+        #[ink(topic)]
+        referrer: AccountId,
+        amount: Balance,
+    }
+
+    /// Emitted when all records of a name are cleared at once.
+    #[ink(event)]
+    pub struct RecordsCleared {
+        #[ink(topic)]
+        node: [u8; 32],
+    }
+
     #[ink(storage)]
-    #[derive(Default)]
+    #[derive(SpreadAllocate)]
     pub struct PeerName {
-        records: HashMap<[u8; 16], AccountId>, // mapping of domain name to owner
-        resolvers: HashMap<[u8; 16], Resolver>, // mapping of domain name to resolver
+        records: Mapping<[u8; 32], AccountId>, // mapping of domain name to owner
+        resolvers: Mapping<[u8; 32], Resolver>, // mapping of domain name to resolver
+        expiries: Mapping<[u8; 32], Timestamp>, // mapping of domain name to expiry timestamp
 
         /// stores admin id of contract
         admin: AccountId,
 
+        /// Proposed admin waiting to accept the role
+        pending_admin: Option<AccountId>,
+
+        /// Whether state changing operations are paused
+        paused: bool,
+
         /// Stores current manager account id of contract
         manager: AccountId,
+
+        /// Proposed manager waiting to accept the role
+        pending_manager: Option<AccountId>,
+
+        /// Seconds after expiry during which only the owner can renew
+        grace_period_secs: u64,
+
+        /// Fee charged for registering a name
+        registration_fee: Balance,
+
+        /// Registration price by name length in bytes
+        length_prices: Mapping<u32, Balance>,
+
+        /// Highest name length with a configured price
+        max_priced_len: Option<u32>,
+
+        /// Text records keyed by node and record key
+        texts: Mapping<([u8; 32], Vec<u8>), Vec<u8>>,
+
+        /// Keys of the text records set on each node
+        text_keys: Mapping<[u8; 32], Vec<Vec<u8>>>,
+
+        /// Address records keyed by node and SLIP-44 coin type
+        addresses: Mapping<([u8; 32], u32), Vec<u8>>,
+
+        /// Coin types of the address records set on each node
+        coin_types: Mapping<[u8; 32], Vec<u32>>,
+
+        /// Content hashes (IPFS, Arweave, ...) of names
+        content_hashes: Mapping<[u8; 32], Vec<u8>>,
+
+        /// IPv4 (A) records of names
+        ipv4s: Mapping<[u8; 32], [u8; 4]>,
+
+        /// IPv6 (AAAA) records of names
+        ipv6s: Mapping<[u8; 32], [u8; 16]>,
+
+        /// How the resolver of each name is to be read, one of the `RECORD_KIND_*` constants
+        record_kinds: Mapping<[u8; 32], u8>,
+
+        /// Readable names of nodes, `subdomain.domain` for subnodes
+        labels: Mapping<[u8; 32], Vec<u8>>,
+
+        /// Accounts approved to transfer a node on behalf of its owner
+        approvals: Mapping<[u8; 32], AccountId>,
+
+        /// Operators approved to manage all names of an owner
+        operator_approvals: Mapping<(AccountId, AccountId), bool>,
+
+        /// Nodes owned by each account
+        owned: Mapping<AccountId, Vec<[u8; 32]>>,
+
+        /// Number of registered names, subdomains included
+        total_names: u32,
+
+        /// Number of registered subdomains
+        total_subdomains: u32,
+
+        /// Minimum length of a label in bytes
+        min_label_len: u32,
+
+        /// Maximum length of a label in bytes
+        max_label_len: u32,
+
+        /// Registration commitments and the time they were made
+        commitments: Mapping<[u8; 32], Timestamp>,
+
+        /// Seconds a commitment has to wait before it can be revealed
+        min_commit_age_secs: u64,
+
+        /// Seconds after which a commitment can no longer be revealed
+        max_commit_age_secs: u64,
+
+        /// Names blocked from registration by the admin
+        reserved: Mapping<[u8; 32], bool>,
+
+        /// Nodes in `reserved` by position, so the blocklist can be listed
+        reserved_keys: Mapping<u32, [u8; 32]>,
+
+        /// Position of each reserved node in `reserved_keys`
+        reserved_positions: Mapping<[u8; 32], u32>,
+
+        /// Number of entries in `reserved_keys`
+        reserved_count: u32,
+
+        /// Seconds off-chain resolvers may cache the records of a name
+        ttls: Mapping<[u8; 32], u32>,
+
+        /// Names their owners locked against transfers
+        locked: Mapping<[u8; 32], bool>,
+
+        /// Accounts allowed to manage the records of a name but not to transfer it
+        controllers: Mapping<[u8; 32], AccountId>,
+
+        /// Subnodes issued under each parent node
+        subnodes: Mapping<[u8; 32], Vec<[u8; 32]>>,
+
+        /// Time each name was registered, see `RESET_REGISTERED_AT_ON_TRANSFER`
+        registered_at: Mapping<[u8; 32], Timestamp>,
+
+        /// Block in which each name was last changed
+        last_modified: Mapping<[u8; 32], BlockNumber>,
+
+        /// Funds each account can pull through `withdraw_balance`
+        balances: Mapping<AccountId, Balance>,
+
+        /// Sum of all `balances`, not available to the admin's `withdraw`
+        total_owed: Balance,
+
+        /// Share of a registration price credited to the referrer, in basis points
+        referral_bps: u16,
+
+        /// Resolver of new registrations passing the zero account as resolver
+        default_resolver: Option<Resolver>,
+
+        /// Recipients of staged transfers, see `accept_transfer`
+        pending_transfers: Mapping<[u8; 32], AccountId>,
+
+        /// Subdomains a parent may have at once, zero means unlimited
+        max_subdomains: u32,
+
+        /// First block of each registrar's current window and its registrations in it
+        registrations_in_window: Mapping<AccountId, (BlockNumber, u32)>,
+
+        /// Length of a rate limiting window in blocks
+        registration_window_blocks: BlockNumber,
+
+        /// Registrations a registrar may make per window, zero means unlimited
+        max_registrations_per_window: u32,
+
+        /// Layout version the storage was last migrated to, see `STORAGE_VERSION`
+        storage_version: u16,
+
+        /// Next nonce of each owner for signed registrations
+        nonces: Mapping<AccountId, u64>,
+
+        /// Registered nodes by position, so all names can be walked across calls
+        node_keys: Mapping<u32, [u8; 32]>,
+
+        /// Position of each registered node in `node_keys`
+        node_positions: Mapping<[u8; 32], u32>,
+
+        /// Number of entries in `node_keys`
+        node_count: u32,
+
+        /// Shortest registration or renewal in seconds
+        min_duration_secs: u64,
+
+        /// Longest registration or renewal in seconds, zero means unlimited
+        max_duration_secs: u64,
+
+        /// Resolvers names may point to while `allowlist_enabled`
+        resolver_allowlist: Mapping<AccountId, bool>,
+
+        /// Whether resolvers are restricted to `resolver_allowlist`
+        allowlist_enabled: bool,
+
+        /// Last `MAX_RESOLVER_HISTORY` resolvers of each name and the blocks they were set in
+        resolver_history: Mapping<[u8; 32], Vec<(Resolver, BlockNumber)>>,
+
+        /// Parents whose owner ruled out new subdomains, see `freeze_subdomains`
+        subdomains_frozen: Mapping<[u8; 32], bool>,
+
+        /// `FUSE_*` bits burned by the owner of each name, see `burn_fuses`
+        fuses: Mapping<[u8; 32], u32>,
     }
 
     impl PeerName {
         #[ink(constructor)]
-        pub fn default(_admin: AccountId, _manager: AccountId) -> Self {
-            Self {
-                records: Default::default(),
-                resolvers: Default::default(),
+        pub fn default(
+            _admin: AccountId,
+            _manager: AccountId,
+            _grace_period_secs: u64,
+            _min_label_len: u32,
+            _max_label_len: u32,
+            _min_commit_age_secs: u64,
+            _max_commit_age_secs: u64,
+        ) -> Self {
+            // Mappings are allocated from the storage layout, the remaining fields start zeroed.
+            ink_lang::codegen::initialize_contract(|contract: &mut Self| {
+                contract.manager = _manager;
+                contract.admin = _admin;
+                contract.grace_period_secs = _grace_period_secs;
+                contract.min_label_len = _min_label_len;
+                contract.max_label_len = _max_label_len;
+                contract.min_commit_age_secs = _min_commit_age_secs;
+                contract.max_commit_age_secs = _max_commit_age_secs;
+                contract.storage_version = STORAGE_VERSION;
+            })
+        }
+
+        /// Deploy with the caller as admin and manager and the `DEFAULT_*` configuration.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            let caller = Self::env().caller();
+            Self::default(
+                caller,
+                caller,
+                DEFAULT_GRACE_PERIOD_SECS,
+                DEFAULT_MIN_LABEL_LEN,
+                DEFAULT_MAX_LABEL_LEN,
+                DEFAULT_MIN_COMMIT_AGE_SECS,
+                DEFAULT_MAX_COMMIT_AGE_SECS,
+            )
+        }
+
+        fn authorized(&self, node: &[u8; 32]) -> bool {
+            self.authorized_for(node, Self::env().caller())
+        }
+
+        /// Whether `who` owns the live node or is an operator of its owner.
+        fn authorized_for(&self, node: &[u8; 32], who: AccountId) -> bool {
+            if self.expired(node) {
+                return false;
+            }
 
-                manager: _manager,
-                admin: _admin,
+            match self.records.get(node) {
+                Some(owner) => owner == who || self.is_approved_for_all(owner, who),
+                None => false,
             }
         }
 
-        fn authorized(&self, node: &[u8; 16]) -> bool {
-            let caller = Self::env().caller();
+        /// Whether the caller is the stored owner of the node, regardless of expiry.
+        fn is_owner(&self, node: &[u8; 32]) -> bool {
+            self.records.get(node) == Some(Self::env().caller())
+        }
 
-            let node_owner = self.records.get(node).cloned();
+        /// Whether the registration of the node has passed its expiry.
+        fn expired(&self, node: &[u8; 32]) -> bool {
+            match self.expiries.get(node) {
+                Some(expiry) => self.env().block_timestamp() > expiry,
+                None => false,
+            }
+        }
 
-            if Some(caller) == node_owner {
-                true
-            } else {
-                false
+        /// Whether the label length is within the configured bounds and its characters are allowed.
+        fn valid_label(&self, label: &[u8]) -> bool {
+            let len = label.len() as u32;
+            len >= self.min_label_len && len <= self.max_label_len && Self::is_valid_label(label)
+        }
+
+        /// Lowercases the ASCII letters of a label, names are case-insensitive.
+        ///
+        /// Other bytes are kept as they are, non-ASCII labels are rejected by `is_valid_label`.
+        fn normalize(label: &[u8]) -> Vec<u8> {
+            label.to_ascii_lowercase()
+        }
+
+        /// Only ASCII lowercase letters, digits and inner hyphens are allowed in a label.
+        fn is_valid_label(label: &[u8]) -> bool {
+            if label.first() == Some(&b'-') || label.last() == Some(&b'-') {
+                return false;
+            }
+            label
+                .iter()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == b'-')
+        }
+
+        /// Whether the caller may manage the records of the node, as owner, operator or controller.
+        fn manages_records(&self, node: &[u8; 32]) -> bool {
+            self.manages_records_for(node, Self::env().caller())
+        }
+
+        /// Whether `who` may manage the records of the node, see `manages_records`.
+        fn manages_records_for(&self, node: &[u8; 32], who: AccountId) -> bool {
+            if self.authorized_for(node, who) {
+                return true;
+            }
+            !self.expired(node) && self.controllers.get(node) == Some(who)
+        }
+
+        /// Whether the node may be transferred to `new_owner`, which must be neither
+        /// the current owner nor the zero account that would strand the name.
+        fn valid_new_owner(&self, node: &[u8; 32], new_owner: AccountId) -> bool {
+            new_owner != AccountId::from([0u8; 32]) && self.records.get(node) != Some(new_owner)
+        }
+
+        /// Like `ensure_authorized`, but the controller of the node is accepted as well.
+        fn ensure_manages_records(&self, node: &[u8; 32]) -> Result<(), Error> {
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.manages_records(node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            Ok(())
+        }
+
+        /// Checks that the node exists, has not expired and the caller may manage it.
+        fn ensure_authorized(&self, node: &[u8; 32]) -> Result<(), Error> {
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.authorized(node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            Ok(())
+        }
+
+        /// Whether the node has expired but is still within its grace period.
+        fn in_grace_period(&self, node: &[u8; 32]) -> bool {
+            match self.expiries.get(node) {
+                Some(expiry) => {
+                    let now = self.env().block_timestamp();
+                    now > expiry && now <= expiry.saturating_add(self.grace_period_ms())
+                }
+                None => false,
+            }
+        }
+
+        /// Whether the node has expired and its grace period has passed.
+        fn released(&self, node: &[u8; 32]) -> bool {
+            match self.expiries.get(node) {
+                Some(expiry) => {
+                    self.env().block_timestamp() > expiry.saturating_add(self.grace_period_ms())
+                }
+                None => false,
             }
         }
 
+        /// Grace period in milliseconds, saturating rather than wrapping.
+        fn grace_period_ms(&self) -> u64 {
+            self.grace_period_secs.saturating_mul(1000)
+        }
+
+        /// The timestamp `secs` seconds after `start`, or `ArithmeticOverflow`.
+        fn add_secs(start: Timestamp, secs: u64) -> Result<Timestamp, Error> {
+            secs.checked_mul(1000)
+                .and_then(|ms| start.checked_add(ms))
+                .ok_or(Error::ArithmeticOverflow)
+        }
+
         /// Register specific name with caller as owner.
-        #[ink(message)]
+        ///
+        /// The registration expires `duration_secs` seconds from now. A name whose
+        /// previous registration expired and passed its grace period can be registered again.
+        /// The transferred value must cover the `cost` of the name, any excess is refunded.
+        /// A commitment of `(domain, owner, secret)` must have been made through `commit`
+        /// between the minimum and maximum commit age ago.
+        ///
+        /// With `overwrite` a live registration is updated to `owner` and `resolver`
+        /// instead of failing with `NameAlreadyExists`, so retried scripts are idempotent.
+        /// The expiry is kept, nothing is charged and no commitment is needed.
+        ///
+        /// A `referrer` other than the caller and the owner is credited `referral_bps`
+        /// of the price in the balances ledger.
+        ///
+        /// Up to `MAX_INITIAL_TEXTS` `(key, value)` text records are set once the owner is.
+        #[ink(message, payable)]
+        #[allow(clippy::too_many_arguments)]
         pub fn register_domain(
             &mut self,
             domain: Vec<u8>,
             owner: AccountId,
             resolver: Resolver,
+            duration_secs: u64,
+            secret: [u8; 32],
+            overwrite: bool,
+            referrer: Option<AccountId>,
+            texts: Vec<(Vec<u8>, Vec<u8>)>,
         ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
             let caller = self.env().caller();
             if caller != self.manager {
                 return Err(Error::UnauthorizedCaller);
             };
-            let node = self.get_node(domain);
-            if self.records.contains_key(&node) {
-                return Err(Error::NameAlreadyExists);
+            if texts.len() > MAX_INITIAL_TEXTS {
+                return Err(Error::BatchTooLarge);
             }
+            self._rate_limit(caller, 1)?;
+            self.check_resolver(self.resolver_or_default(resolver))?;
+            let node = match self.check_registrable(&domain) {
+                Ok(node) => node,
+                Err(Error::NameAlreadyExists) if overwrite => {
+                    let node = self.get_node(domain);
+                    self._overwrite(node, owner, resolver)?;
+                    self._set_texts(node, texts);
+                    return Ok(());
+                }
+                Err(error) => return Err(error),
+            };
+            self.check_duration(duration_secs)?;
+            let price = self.cost_of(&domain, duration_secs)?;
+            let transferred = self.env().transferred_balance();
+            if transferred < price {
+                return Err(Error::InsufficientFee);
+            }
+            let commitment = self.make_commitment(domain.clone(), owner, secret);
+            let committed_at = match self.commitments.get(commitment) {
+                Some(committed_at) => committed_at,
+                None => return Err(Error::CommitmentNotFound),
+            };
+            let age = self.env().block_timestamp() - committed_at;
+            if age < self.min_commit_age_secs.saturating_mul(1000) {
+                return Err(Error::CommitmentTooNew);
+            }
+            if age > self.max_commit_age_secs.saturating_mul(1000) {
+                return Err(Error::CommitmentNotFound);
+            }
+            let expiry = Self::add_secs(self.env().block_timestamp(), duration_secs)?;
 
-            self._set_owner(node, owner);
-            self._set_resolver(node, resolver);
-            self.env().emit_event(Register { node, from: owner });
+            // Return any overpayment before touching storage.
+            if transferred > price {
+                self.env()
+                    .transfer(caller, transferred - price)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            self.commitments.remove(commitment);
+            self._register(node, domain, owner, resolver, expiry);
+            self._set_texts(node, texts);
+            if let Some(referrer) = referrer {
+                if referrer != caller && referrer != owner {
+                    self._pay_referral(referrer, price)?;
+                }
+            }
 
             Ok(())
         }
 
-        /// Register specific name with caller as owner.
-        #[ink(message)]
-        pub fn set_sub_domain(
+        /// Register several names to the same owner and resolver in one call.
+        ///
+        /// Manager only like `register_domain`, but without commitments. Every name is
+        /// checked before any is registered, so the first error aborts the whole batch.
+        /// The transferred value must cover the summed `cost`, any excess is refunded.
+        #[ink(message, payable)]
+        pub fn batch_register(
             &mut self,
-            domain: Vec<u8>,
-            subdomain: Vec<u8>,
+            domains: Vec<Vec<u8>>,
+            owner: AccountId,
             resolver: Resolver,
+            duration_secs: u64,
         ) -> Result<(), Error> {
-            let caller = self.env().caller();
-
-            let node = self.get_node(domain.clone());
-            if !self.records.contains_key(&node) {
-                return Err(Error::NameNotExists);
+            if self.paused {
+                return Err(Error::Paused);
             }
-            if !self.authorized(&node) {
+            let caller = self.env().caller();
+            if caller != self.manager {
                 return Err(Error::UnauthorizedCaller);
+            };
+            if domains.len() > MAX_BATCH_SIZE {
+                return Err(Error::BatchTooLarge);
             }
-            let subnode = self.get_subnode(domain, subdomain);
+            self.check_duration(duration_secs)?;
+            self.check_resolver(self.resolver_or_default(resolver))?;
 
-            if self.records.contains_key(&subnode) {
-                return Err(Error::NameAlreadyExists);
+            let mut nodes: Vec<[u8; 32]> = Vec::new();
+            let mut price: Balance = 0;
+            for domain in domains.iter() {
+                let node = self.check_registrable(domain)?;
+                if nodes.contains(&node) {
+                    return Err(Error::NameAlreadyExists);
+                }
+                nodes.push(node);
+                price = price
+                    .checked_add(self.cost_of(domain, duration_secs)?)
+                    .ok_or(Error::ArithmeticOverflow)?;
+            }
+            let expiry = Self::add_secs(self.env().block_timestamp(), duration_secs)?;
+            let transferred = self.env().transferred_balance();
+            if transferred < price {
+                return Err(Error::InsufficientFee);
+            }
+            self._rate_limit(caller, nodes.len() as u32)?;
+
+            // Return any overpayment before touching storage.
+            if transferred > price {
+                self.env()
+                    .transfer(caller, transferred - price)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            for (node, domain) in nodes.into_iter().zip(domains.into_iter()) {
+                self._register(node, domain, owner, resolver, expiry);
             }
-            // self._set_record(subnode, caller, resolver);
-            self._set_owner(subnode, caller);
-            self._set_resolver(subnode, resolver);
-            self.env().emit_event(Register {
-                node: subnode,
-                from: caller,
-            });
 
             Ok(())
         }
 
-        /// update node resolver
-        #[ink(message)]
-        pub fn update_domain_resolver(
+        /// Register a name on behalf of an `owner` who signed the registration off-chain.
+        ///
+        /// Manager only like `register_domain`, so nobody can front-run a relayed signature
+        /// with one of their own. The manager pays the price, any excess is refunded. The
+        /// `signature` is the owner's ECDSA signature of `registration_hash`, where the
+        /// owner is the Blake2x256 hash of the compressed public key. The `nonce` has to be
+        /// `nonce_of(owner)` and is used up, so a signature cannot be replayed.
+        #[ink(message, payable)]
+        pub fn register_domain_with_sig(
             &mut self,
             domain: Vec<u8>,
+            owner: AccountId,
             resolver: Resolver,
+            duration_secs: u64,
+            nonce: u64,
+            signature: [u8; 65],
         ) -> Result<(), Error> {
-            let node = self.get_node(domain);
-
-            if !self.records.contains_key(&node) {
-                return Err(Error::NameNotExists);
+            if self.paused {
+                return Err(Error::Paused);
             }
-            if !self.authorized(&node) {
+            let caller = self.env().caller();
+            if caller != self.manager {
                 return Err(Error::UnauthorizedCaller);
+            };
+            let node = self.check_registrable(&domain)?;
+            self.check_duration(duration_secs)?;
+            self.check_resolver(self.resolver_or_default(resolver))?;
+            if nonce != self.nonce_of(owner) {
+                return Err(Error::BadNonce);
             }
-            self._set_resolver(node, resolver);
+            let hash =
+                self.registration_hash(domain.clone(), owner, resolver, duration_secs, nonce);
+            let mut public_key = [0u8; 33];
+            ink_env::ecdsa_recover(&signature, &hash, &mut public_key)
+                .map_err(|_| Error::InvalidSignature)?;
+            let mut signer = <Blake2x256 as HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(&public_key, &mut signer);
+            if AccountId::from(signer) != owner {
+                return Err(Error::InvalidSignature);
+            }
+            let price = self.cost_of(&domain, duration_secs)?;
+            let transferred = self.env().transferred_balance();
+            if transferred < price {
+                return Err(Error::InsufficientFee);
+            }
+            let expiry = Self::add_secs(self.env().block_timestamp(), duration_secs)?;
+            let next_nonce = nonce.checked_add(1).ok_or(Error::ArithmeticOverflow)?;
+            self._rate_limit(caller, 1)?;
+
+            // Return any overpayment before touching storage.
+            if transferred > price {
+                self.env()
+                    .transfer(caller, transferred - price)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            self.nonces.insert(owner, &next_nonce);
+            self._register(node, domain, owner, resolver, expiry);
+
             Ok(())
         }
 
-        /// update node resolver
+        /// Message an owner signs for `register_domain_with_sig`
+        ///
+        /// Blake2x256 of the SCALE encoded `(contract, domain, owner, resolver, duration_secs,
+        /// nonce)` tuple, the contract account keeps signatures from being used elsewhere.
         #[ink(message)]
-        pub fn update_subdomain_resolver(
-            &mut self,
+        pub fn registration_hash(
+            &self,
             domain: Vec<u8>,
+            owner: AccountId,
+            resolver: Resolver,
+            duration_secs: u64,
+            nonce: u64,
+        ) -> [u8; 32] {
+            let encodable = (
+                self.env().account_id(),
+                domain,
+                owner,
+                resolver,
+                duration_secs,
+                nonce,
+            ); // Implements `scale::Encode`
+            let mut output = <Blake2x256 as HashOutput>::Type::default(); // 256-bit buffer
+            ink_env::hash_encoded::<Blake2x256, _>(&encodable, &mut output);
+            output
+        }
+
+        /// Nonce the next signed registration of `owner` has to use
+        #[ink(message)]
+        pub fn nonce_of(&self, owner: AccountId) -> u64 {
+            self.nonces.get(owner).unwrap_or_default()
+        }
+
+        /// Checks a resolver against the allowlist, the zero account stands for no resolver.
+        fn check_resolver(&self, resolver: Resolver) -> Result<(), Error> {
+            if resolver != AccountId::from([0u8; 32]) && !self.is_resolver_allowed(resolver) {
+                return Err(Error::ResolverNotAllowed);
+            }
+            Ok(())
+        }
+
+        /// Fails if any of the given fuses is burned for the node.
+        fn check_fuses(&self, node: &[u8; 32], fuses: u32) -> Result<(), Error> {
+            if self.fuses.get(node).unwrap_or_default() & fuses != 0 {
+                return Err(Error::FuseBurned);
+            }
+            Ok(())
+        }
+
+        /// Checks a registration or renewal period against the admin's duration limits.
+        fn check_duration(&self, duration_secs: u64) -> Result<(), Error> {
+            if duration_secs < self.min_duration_secs {
+                return Err(Error::DurationTooShort);
+            }
+            if self.max_duration_secs > 0 && duration_secs > self.max_duration_secs {
+                return Err(Error::DurationTooLong);
+            }
+            Ok(())
+        }
+
+        /// Counts `count` registrations of `registrar` against its current window.
+        fn _rate_limit(&mut self, registrar: AccountId, count: u32) -> Result<(), Error> {
+            if self.max_registrations_per_window == 0 {
+                return Ok(());
+            }
+            let now = self.env().block_number();
+            let (start, used) = match self.registrations_in_window.get(registrar) {
+                Some((start, used))
+                    if now < start.saturating_add(self.registration_window_blocks) =>
+                {
+                    (start, used)
+                }
+                _ => (now, 0),
+            };
+            let used = used.saturating_add(count);
+            if used > self.max_registrations_per_window {
+                return Err(Error::RateLimited);
+            }
+            self.registrations_in_window.insert(registrar, &(start, used));
+            Ok(())
+        }
+
+        /// Credits the referrer its share of a registration `price`.
+        fn _pay_referral(&mut self, referrer: AccountId, price: Balance) -> Result<(), Error> {
+            let amount = price
+                .checked_mul(self.referral_bps as Balance)
+                .ok_or(Error::ArithmeticOverflow)?
+                / 10_000;
+            if amount > 0 {
+                self._credit(referrer, amount)?;
+                self.env().emit_event(ReferralPaid { referrer, amount });
+            }
+            Ok(())
+        }
+
+        /// Points a live registration at `owner` and `resolver`, refunding any transferred value.
+        fn _overwrite(
+            &mut self,
+            node: [u8; 32],
+            owner: AccountId,
             resolver: Resolver,
-            subdomain: Vec<u8>,
         ) -> Result<(), Error> {
-            let node = self.get_node(domain.clone());
+            let transferred = self.env().transferred_balance();
+            if transferred > 0 {
+                self.env()
+                    .transfer(self.env().caller(), transferred)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            if self.records.get(node) != Some(owner) {
+                self._transfer(node, owner);
+            }
+            self._set_resolver(node, self.resolver_or_default(resolver));
+            Ok(())
+        }
 
-            if !self.records.contains_key(&node) {
-                return Err(Error::NameNotExists);
+        /// The default resolver in place of the zero account, if the admin set one.
+        fn resolver_or_default(&self, resolver: Resolver) -> Resolver {
+            if resolver == AccountId::from([0u8; 32]) {
+                self.default_resolver.unwrap_or(resolver)
+            } else {
+                resolver
             }
-            if !self.authorized(&node) {
-                return Err(Error::UnauthorizedCaller);
+        }
+
+        /// Checks that the domain can be registered and returns its node.
+        fn check_registrable(&self, domain: &[u8]) -> Result<[u8; 32], Error> {
+            if !self.valid_label(&Self::normalize(domain)) {
+                return Err(Error::InvalidName);
+            }
+            let node = self.get_node(domain.to_vec());
+            if self.reserved.contains(node) {
+                return Err(Error::NameReserved);
+            }
+            if self.in_grace_period(&node) {
+                return Err(Error::InGracePeriod);
+            }
+            if self.records.contains(node) && !self.released(&node) {
+                return Err(Error::NameAlreadyExists);
+            }
+            Ok(node)
+        }
+
+        /// Stores the registration of a name that passed `check_registrable`.
+        fn _register(
+            &mut self,
+            node: [u8; 32],
+            domain: Vec<u8>,
+            owner: AccountId,
+            resolver: Resolver,
+            expiry: Timestamp,
+        ) {
+            let domain = Self::normalize(&domain);
+            if let Some(previous_owner) = self.records.get(node) {
+                // The previous registration has lapsed, none of its records carry over and
+                // the new owner is announced with `NewOwner` like on a first registration.
+                self.records.remove(node);
+                self.remove_owned(previous_owner, &node);
+                self._clear_node(node);
+            } else {
+                self.total_names += 1;
             }
 
-            let subnode = self.get_subnode(domain, subdomain);
-            self._set_resolver(subnode, resolver);
+            let resolver = self.resolver_or_default(resolver);
+            self._set_owner(node, owner);
+            self._set_resolver(node, resolver);
+            self.registered_at.insert(node, &self.env().block_timestamp());
+            self.expiries.insert(node, &expiry);
+            self.labels.insert(node, &domain);
+            self.env().emit_event(Register {
+                node,
+                from: owner,
+                registrar: self.env().caller(),
+                label: domain,
+                resolver: Some(resolver),
+            });
+        }
+
+        /// Commit to a later registration, see `make_commitment`
+        ///
+        /// A live commitment keeps its original timestamp when committed again.
+        #[ink(message)]
+        pub fn commit(&mut self, commitment: [u8; 32]) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let now = self.env().block_timestamp();
+            let live = match self.commitments.get(commitment) {
+                Some(committed_at) => {
+                    now - committed_at <= self.max_commit_age_secs.saturating_mul(1000)
+                }
+                None => false,
+            };
+            if !live {
+                self.commitments.insert(commitment, &now);
+            }
             Ok(())
         }
 
-        /// owner of a node transfers ownership to a new account.
+        /// calculate the registration commitment
+        ///
+        /// Blake2x256 of the SCALE encoded `(domain, owner, secret)` tuple.
+        #[inline]
+        pub fn make_commitment(
+            &self,
+            domain: Vec<u8>,
+            owner: AccountId,
+            secret: [u8; 32],
+        ) -> [u8; 32] {
+            let encodable = (domain, owner, secret); // Implements `scale::Encode`
+            let mut output = <Blake2x256 as HashOutput>::Type::default(); // 256-bit buffer
+            ink_env::hash_encoded::<Blake2x256, _>(&encodable, &mut output);
+            output
+        }
+
+        /// Register a subdomain of a name the caller may manage.
+        ///
+        /// The subdomain is owned by `owner`, which does not have to be the caller.
+        /// Passing the zero account as `resolver` makes the subdomain inherit the
+        /// resolver of the parent name, if it has one.
         #[ink(message)]
-        pub fn transfer_domain_ownership(
+        pub fn set_sub_domain(
             &mut self,
             domain: Vec<u8>,
-            new_owner: AccountId,
+            subdomain: Vec<u8>,
+            owner: AccountId,
+            resolver: Resolver,
         ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let domain = Self::normalize(&domain);
+            let subdomain = Self::normalize(&subdomain);
+            if !self.valid_label(&subdomain) {
+                return Err(Error::InvalidName);
+            }
+
             let node = self.get_node(domain.clone());
-            if !self.records.contains_key(&node) {
+            if !self.records.contains(node) {
                 return Err(Error::NameNotExists);
             }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
             if !self.authorized(&node) {
                 return Err(Error::UnauthorizedCaller);
             }
+            self.check_resolver(resolver)?;
+            let mut label = subdomain.clone();
+            label.push(b'.');
+            label.extend_from_slice(&domain);
+            let subnode = self.get_subnode(domain, subdomain);
 
-            self._set_owner(node, new_owner);
-            self.env().emit_event(Transfer {
+            if self.records.contains(subnode) {
+                return Err(Error::NameAlreadyExists);
+            }
+            if self.subdomains_frozen.contains(node) {
+                return Err(Error::SubdomainsFrozen);
+            }
+            self.check_fuses(&node, FUSE_CANNOT_CREATE_SUBDOMAIN)?;
+            let mut subnodes = self.subnodes.get(node).unwrap_or_default();
+            if self.max_subdomains > 0 && subnodes.len() >= self.max_subdomains as usize {
+                return Err(Error::SubdomainLimitReached);
+            }
+            // self._set_record(subnode, caller, resolver);
+            self._set_owner(subnode, owner);
+            let resolver = if resolver == AccountId::from([0u8; 32]) {
+                self.resolvers.get(node)
+            } else {
+                Some(resolver)
+            };
+            if let Some(resolver) = resolver {
+                self._set_resolver(subnode, resolver);
+            }
+            self.labels.insert(subnode, &label);
+            if !subnodes.contains(&subnode) {
+                subnodes.push(subnode);
+                self.subnodes.insert(node, &subnodes);
+            }
+            self.total_names += 1;
+            self.total_subdomains += 1;
+            self.env().emit_event(Register {
+                node: subnode,
+                from: owner,
+                registrar: self.env().caller(),
+                label,
+                resolver,
+            });
+            self.env().emit_event(SubNode {
+                owner,
                 node,
-                owner: new_owner,
+                subnode,
             });
 
             Ok(())
         }
 
-        /// Node exist or note
+        /// Set the resolver of a domain that has none, fails with `ResolverAlreadySet` otherwise
         #[ink(message)]
-        pub fn is_domain_exist(&self, domain: Vec<u8>) -> bool {
+        pub fn set_resolver_if_absent(
+            &mut self,
+            domain: Vec<u8>,
+            resolver: Resolver,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
             let node = self.get_node(domain);
-            if self.records.contains_key(&node) {
-                true
-            } else {
-                false
+            self.ensure_manages_records(&node)?;
+            self.check_resolver(resolver)?;
+            self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+            if self.pending_transfers.contains(node) {
+                return Err(Error::TransferPending);
             }
+            if self.resolvers.contains(node) {
+                return Err(Error::ResolverAlreadySet);
+            }
+            self._set_resolver(node, resolver);
+            Ok(())
         }
 
-        /// renounce ownership by manager
+        /// update node resolver
         #[ink(message)]
-        pub fn renounce_ownership(&mut self ,  domain: Vec<u8>) -> Result<(), Error> {
-            let caller = self.env().caller();
-            let  node = self.get_node(domain);
-            if caller != self.manager {
-                return Err(Error::UnauthorizedCaller);
-            };       
-            if !self.records.contains_key(&node) {
+        pub fn update_domain_resolver(
+            &mut self,
+            domain: Vec<u8>,
+            resolver: Resolver,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+
+            if !self.records.contains(node) {
                 return Err(Error::NameNotExists);
             }
-           self.records.take(&node);
-           Ok(())
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.manages_records(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            self.check_resolver(resolver)?;
+            self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+            if self.pending_transfers.contains(node) {
+                return Err(Error::TransferPending);
+            }
+            self._set_resolver(node, resolver);
+            Ok(())
         }
 
-
-        /// renounce ownership by oner only
+        /// Set a text record of the domain, an empty value removes the record
         #[ink(message)]
-        pub fn renounce_my_ownership(&mut self ,  domain: Vec<u8>) -> Result<(), Error> {
-            let  node = self.get_node(domain);
-            if !self.authorized(&node) {
-                return Err(Error::UnauthorizedCaller);
-            };       
-            if !self.records.contains_key(&node) {
-                return Err(Error::NameNotExists);
+        pub fn set_text(
+            &mut self,
+            domain: Vec<u8>,
+            key: Vec<u8>,
+            value: Vec<u8>,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
             }
-           self.records.take(&node);
-           Ok(())
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+
+            self._set_text(node, key, value);
+
+            Ok(())
         }
 
-        /// SubNode exist or note
-        #[ink(message)]
-        pub fn is_subdomain_exist(&self, domain: Vec<u8>, subdomain: Vec<u8>) -> bool {
-            let subnode = self.get_subnode(domain, subdomain);
-            if self.records.contains_key(&subnode) {
-                true
+        /// Stores a text record, an empty value removes it, and emits `TextChanged`.
+        fn _set_text(&mut self, node: [u8; 32], key: Vec<u8>, value: Vec<u8>) {
+            let mut keys = self.text_keys.get(node).unwrap_or_default();
+            if value.is_empty() {
+                self.texts.remove(&(node, key.clone()));
+                keys.retain(|existing| existing != &key);
             } else {
-                false
+                self.texts.insert(&(node, key.clone()), &value);
+                if !keys.contains(&key) {
+                    keys.push(key.clone());
+                }
             }
+            self.text_keys.insert(node, &keys);
+            self.touch(node);
+            self.env().emit_event(TextChanged { node, key });
         }
 
-        fn _set_owner(&mut self, node: [u8; 16], owner: AccountId) -> bool {
-            // let node = self.get_node(domain);
-            self.records.insert(node, owner);
-            self.env().emit_event(Transfer {
-                node: node,
-                owner: owner,
-            });
-
-            return true;
+        /// `_set_text` for each `(key, value)` pair.
+        fn _set_texts(&mut self, node: [u8; 32], texts: Vec<(Vec<u8>, Vec<u8>)>) {
+            for (key, value) in texts {
+                self._set_text(node, key, value);
+            }
         }
 
-        fn _set_resolver(&mut self, node: [u8; 16], resolver: Resolver) {
-            // let node = self.get_node(domain);
-
-            self.resolvers.insert(node, resolver);
-            self.env().emit_event(NewResolver { node, resolver });
+        /// Text record of the domain for the given key
+        #[ink(message)]
+        pub fn get_text(&self, domain: Vec<u8>, key: Vec<u8>) -> Option<Vec<u8>> {
+            let node = self.get_node(domain);
+            self.texts.get(&(node, key))
         }
 
-        /// Current manager of contract
+        /// Keys of all text records of the domain, in the order they were first set
         #[ink(message)]
-        pub fn current_manager(&self) -> AccountId {
-            self.manager
+        pub fn text_keys(&self, domain: Vec<u8>) -> Vec<Vec<u8>> {
+            let node = self.get_node(domain);
+            self.text_keys.get(node).unwrap_or_default()
         }
 
-        /// Admin of the contract
+        /// Set the address of the domain for a SLIP-44 coin type, an empty address removes it
+        ///
+        /// `NATIVE_COIN_TYPE` sets the domain resolver and requires a 32 byte account id.
         #[ink(message)]
-        pub fn admin(&self) -> AccountId {
-            self.admin
+        pub fn set_addr(
+            &mut self,
+            domain: Vec<u8>,
+            coin_type: u32,
+            addr: Vec<u8>,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+
+            if coin_type == NATIVE_COIN_TYPE {
+                let resolver =
+                    Resolver::try_from(addr.as_slice()).map_err(|_| Error::InvalidAddress)?;
+                self.check_resolver(resolver)?;
+                self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+                if self.pending_transfers.contains(node) {
+                    return Err(Error::TransferPending);
+                }
+                self._set_resolver(node, resolver);
+            } else {
+                let mut coin_types = self.coin_types.get(node).unwrap_or_default();
+                coin_types.retain(|existing| *existing != coin_type);
+                if addr.is_empty() {
+                    self.addresses.remove((node, coin_type));
+                } else {
+                    self.addresses.insert((node, coin_type), &addr);
+                    coin_types.push(coin_type);
+                }
+                self.coin_types.insert(node, &coin_types);
+            }
+            self.touch(node);
+            self.env().emit_event(AddrChanged { node, coin_type });
+
+            Ok(())
         }
 
-        /// Only Admin can change the current manager
+        /// Address of the domain for a SLIP-44 coin type
+        ///
+        /// `NATIVE_COIN_TYPE` returns the domain resolver.
         #[ink(message)]
-        pub fn change_manager(&mut self, _manager: AccountId) -> Result<(), Error> {
-            let caller = self.env().caller();
-            if caller != self.admin {
-                return Err(Error::UnauthorizedCaller);
-            };
+        pub fn addr(&self, domain: Vec<u8>, coin_type: u32) -> Option<Vec<u8>> {
+            let node = self.get_node(domain);
+            if coin_type == NATIVE_COIN_TYPE {
+                return self
+                    .resolvers
+                    .get(node)
+                    .map(|resolver| AsRef::<[u8]>::as_ref(&resolver).to_vec());
+            }
+            self.addresses.get((node, coin_type))
+        }
 
-            self.manager = _manager;
+        /// Set the content hash of the domain, an empty hash removes the record
+        #[ink(message)]
+        pub fn set_content_hash(&mut self, domain: Vec<u8>, hash: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
 
-            self.env().emit_event(ChangeManager {
-                _current_manager: Some(self.manager),
-                _new_manager: Some(_manager),
-            });
+            if hash.is_empty() {
+                self.content_hashes.remove(node);
+            } else {
+                self.content_hashes.insert(node, &hash);
+            }
+            self.touch(node);
+            self.env().emit_event(ContentHashChanged { node });
 
             Ok(())
         }
 
-        /// calculate subnode from lable
+        /// Content hash of the domain
         #[ink(message)]
-        pub fn get_node(&self, domain: Vec<u8>) -> [u8; 16] {
-            let encodable = domain; // Implements `scale::Encode`
-            let mut output = <Blake2x128 as HashOutput>::Type::default(); // 256-bit buffer
-            ink_env::hash_encoded::<Blake2x128, _>(&encodable, &mut output);
-            output
+        pub fn content_hash(&self, domain: Vec<u8>) -> Option<Vec<u8>> {
+            let node = self.get_node(domain);
+            self.content_hashes.get(node)
         }
 
-        /// calculate subnode from lable
+        /// Set the IPv4 address of the domain
         #[ink(message)]
-        pub fn get_subnode(&self, domain: Vec<u8>, subdomain: Vec<u8>) -> [u8; 16] {
-            let encodable = (domain, subdomain); // Implements `scale::Encode`
-            let mut output = <Blake2x128 as HashOutput>::Type::default(); // 256-bit buffer
-            ink_env::hash_encoded::<Blake2x128, _>(&encodable, &mut output);
-            output
+        pub fn set_ipv4(&mut self, domain: Vec<u8>, addr: [u8; 4]) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+
+            self.ipv4s.insert(node, &addr);
+            self.touch(node);
+            self.env().emit_event(Ipv4Changed { node, addr });
+
+            Ok(())
         }
 
+        /// IPv4 address of the domain
         #[ink(message)]
-        pub fn owner(&self, domain: Vec<u8>) -> Option<AccountId> {
+        pub fn ipv4(&self, domain: Vec<u8>) -> Option<[u8; 4]> {
             let node = self.get_node(domain);
-            self.records.get(&node).cloned()
-            //self.token_approvals.get(&id).cloned()
+            self.ipv4s.get(node)
         }
 
-        //    /// Returns the approved account ID for this token if any.
-        //    #[ink(message)]
-        //    pub fn get_approved(&self, id: TokenId) -> Option<AccountId> {
-        //        self.token_approvals.get(&id).cloned()
-        //    }
-
+        /// Set the IPv6 address of the domain
         #[ink(message)]
-        pub fn domain_resolver(&self, domain: Vec<u8>) -> Option<Resolver> {
+        pub fn set_ipv6(&mut self, domain: Vec<u8>, addr: [u8; 16]) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
             let node = self.get_node(domain);
-            return self.resolvers.get(&node).cloned();
+            self.ensure_manages_records(&node)?;
+
+            self.ipv6s.insert(node, &addr);
+            self.touch(node);
+            self.env().emit_event(Ipv6Changed { node, addr });
+
+            Ok(())
         }
+
+        /// IPv6 address of the domain
+        #[ink(message)]
+        pub fn ipv6(&self, domain: Vec<u8>) -> Option<[u8; 16]> {
+            let node = self.get_node(domain);
+            self.ipv6s.get(node)
+        }
+
+        /// Tag how the resolver of the domain is to be read, one of the `RECORD_KIND_*` constants
+        #[ink(message)]
+        pub fn set_record_kind(&mut self, domain: Vec<u8>, kind: u8) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+            match kind {
+                RECORD_KIND_WALLET | RECORD_KIND_CONTRACT | RECORD_KIND_IPFS | RECORD_KIND_IPV4
+                | RECORD_KIND_IPV6 => {}
+                _ => return Err(Error::InvalidRecordKind),
+            }
+
+            self.record_kinds.insert(node, &kind);
+            self.touch(node);
+            self.env().emit_event(RecordKindChanged { node, kind });
+
+            Ok(())
+        }
+
+        /// Record kind of the domain, `RECORD_KIND_WALLET` unless tagged otherwise
+        #[ink(message)]
+        pub fn record_kind(&self, domain: Vec<u8>) -> u8 {
+            let node = self.get_node(domain);
+            self.record_kinds.get(node).unwrap_or(RECORD_KIND_WALLET)
+        }
+
+        /// Remove the resolver, content hash, TTL, IP addresses and all text and address records
+        ///
+        /// The ownership of the domain is kept.
+        #[ink(message)]
+        pub fn clear_records(&mut self, domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+            self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+            if self.pending_transfers.contains(node) {
+                return Err(Error::TransferPending);
+            }
+
+            self.resolvers.remove(node);
+            self.record_kinds.remove(node);
+            self.content_hashes.remove(node);
+            self.ipv4s.remove(node);
+            self.ipv6s.remove(node);
+            self.ttls.remove(node);
+            self._clear_texts_and_addresses(&node);
+            self.touch(node);
+            self.env().emit_event(RecordsCleared { node });
+
+            Ok(())
+        }
+
+        /// Set how many seconds off-chain resolvers may cache the domain records
+        #[ink(message)]
+        pub fn set_ttl(&mut self, domain: Vec<u8>, ttl: u32) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_manages_records(&node)?;
+
+            self.ttls.insert(node, &ttl);
+            self.touch(node);
+            self.env().emit_event(TtlChanged { node, ttl });
+
+            Ok(())
+        }
+
+        /// Caching hint of the domain in seconds, 0 if not set
+        #[ink(message)]
+        pub fn ttl(&self, domain: Vec<u8>) -> u32 {
+            let node = self.get_node(domain);
+            self.ttls.get(node).unwrap_or_default()
+        }
+
+        /// update node resolver
+        #[ink(message)]
+        pub fn update_subdomain_resolver(
+            &mut self,
+            domain: Vec<u8>,
+            resolver: Resolver,
+            subdomain: Vec<u8>,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain.clone());
+
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.manages_records(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            self.check_resolver(resolver)?;
+            // A parent that burned the fuse gave up repointing its subdomains as well.
+            self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+
+            let subnode = self.get_subnode(domain, subdomain);
+            self._set_resolver(subnode, resolver);
+            Ok(())
+        }
+
+        /// owner of a node transfers ownership to a new account.
+        ///
+        /// Returns the previous owner, so escrow flows can check who the name moved away from.
+        /// A `staged` transfer only takes effect once `new_owner` calls `accept_transfer`,
+        /// until then the resolver of the name cannot be changed.
+        #[ink(message)]
+        pub fn transfer_domain_ownership(
+            &mut self,
+            domain: Vec<u8>,
+            new_owner: AccountId,
+            staged: bool,
+        ) -> Result<AccountId, Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain.clone());
+            let previous_owner = match self.records.get(node) {
+                Some(owner) => owner,
+                None => return Err(Error::NameNotExists),
+            };
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.authorized(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            if self.locked.contains(node) {
+                return Err(Error::DomainLocked);
+            }
+            self.check_fuses(&node, FUSE_CANNOT_TRANSFER)?;
+            if !self.valid_new_owner(&node, new_owner) {
+                return Err(Error::InvalidOwner);
+            }
+
+            if staged {
+                self.pending_transfers.insert(node, &new_owner);
+                self.env().emit_event(PendingTransferChanged {
+                    node,
+                    recipient: Some(new_owner),
+                });
+            } else {
+                self._transfer(node, new_owner);
+            }
+
+            Ok(previous_owner)
+        }
+
+        /// Recipient of a staged transfer takes over the name.
+        #[ink(message)]
+        pub fn accept_transfer(&mut self, domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            let caller = self.env().caller();
+            if self.pending_transfers.get(node) != Some(caller) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            if self.locked.contains(node) {
+                return Err(Error::DomainLocked);
+            }
+            self.check_fuses(&node, FUSE_CANNOT_TRANSFER)?;
+
+            self._transfer(node, caller);
+
+            Ok(())
+        }
+
+        /// Owner of a name withdraws its staged transfer, which unfreezes the resolver.
+        #[ink(message)]
+        pub fn cancel_transfer(&mut self, domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            if !self.pending_transfers.contains(node) {
+                return Err(Error::NoPendingTransfer);
+            }
+
+            self.pending_transfers.remove(node);
+            self.env().emit_event(PendingTransferChanged {
+                node,
+                recipient: None,
+            });
+
+            Ok(())
+        }
+
+        /// Recipient of the staged transfer of a domain, if any
+        #[ink(message)]
+        pub fn pending_transfer(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            self.pending_transfers.get(node)
+        }
+
+        /// owner of a node sets its owner and resolver in one call.
+        ///
+        /// Only the current owner can call it. Keeping the owner only updates the resolver.
+        #[ink(message)]
+        pub fn set_record(
+            &mut self,
+            domain: Vec<u8>,
+            new_owner: AccountId,
+            resolver: Resolver,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            self.check_resolver(resolver)?;
+            if self.pending_transfers.contains(node) {
+                return Err(Error::TransferPending);
+            }
+            let owner_changes = new_owner != self.env().caller();
+            if owner_changes && self.locked.contains(node) {
+                return Err(Error::DomainLocked);
+            }
+            if owner_changes {
+                self.check_fuses(&node, FUSE_CANNOT_TRANSFER)?;
+            }
+            if self.resolvers.get(node) != Some(resolver) {
+                self.check_fuses(&node, FUSE_CANNOT_SET_RESOLVER)?;
+            }
+            if owner_changes && !self.valid_new_owner(&node, new_owner) {
+                return Err(Error::InvalidOwner);
+            }
+
+            self._set_resolver(node, resolver);
+            if owner_changes {
+                self._transfer(node, new_owner);
+            }
+
+            Ok(())
+        }
+
+        /// owner of several nodes transfers all of them to a new account.
+        ///
+        /// Authorization is checked for every name before any is transferred.
+        #[ink(message)]
+        pub fn batch_transfer(
+            &mut self,
+            domains: Vec<Vec<u8>>,
+            new_owner: AccountId,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            if domains.len() > MAX_BATCH_SIZE {
+                return Err(Error::BatchTooLarge);
+            }
+
+            let mut nodes: Vec<[u8; 32]> = Vec::new();
+            for domain in domains {
+                let node = self.get_node(domain);
+                if !self.authorized(&node) {
+                    return Err(Error::UnauthorizedCaller);
+                }
+                if self.locked.contains(node) {
+                    return Err(Error::DomainLocked);
+                }
+                self.check_fuses(&node, FUSE_CANNOT_TRANSFER)?;
+                if !self.valid_new_owner(&node, new_owner) {
+                    return Err(Error::InvalidOwner);
+                }
+                nodes.push(node);
+            }
+            for node in nodes {
+                self._transfer(node, new_owner);
+            }
+
+            Ok(())
+        }
+
+        /// Approve `to` to transfer the domain on behalf of the owner
+        #[ink(message)]
+        pub fn approve(&mut self, domain: Vec<u8>, to: AccountId) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            self.ensure_authorized(&node)?;
+
+            self.approvals.insert(node, &to);
+
+            Ok(())
+        }
+
+        /// Account approved to transfer the domain, if any
+        #[ink(message)]
+        pub fn get_approved(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            self.approvals.get(node)
+        }
+
+        /// Enable or disable `operator` to manage all names of the caller
+        #[ink(message)]
+        pub fn set_approval_for_all(
+            &mut self,
+            operator: AccountId,
+            approved: bool,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let caller = self.env().caller();
+            if approved {
+                self.operator_approvals.insert((caller, operator), &true);
+            } else {
+                self.operator_approvals.remove((caller, operator));
+            }
+            self.env().emit_event(ApprovalForAll {
+                owner: caller,
+                operator,
+                approved,
+            });
+
+            Ok(())
+        }
+
+        /// Whether `operator` may manage all names of `owner`
+        #[ink(message)]
+        pub fn is_approved_for_all(&self, owner: AccountId, operator: AccountId) -> bool {
+            self.operator_approvals
+                .get((owner, operator))
+                .unwrap_or(false)
+        }
+
+        /// owner or approved account transfers ownership to a new account.
+        #[ink(message)]
+        pub fn transfer_from(
+            &mut self,
+            domain: Vec<u8>,
+            new_owner: AccountId,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let caller = self.env().caller();
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.authorized(&node) && self.approvals.get(node) != Some(caller) {
+                return Err(Error::NotApproved);
+            }
+            if self.locked.contains(node) {
+                return Err(Error::DomainLocked);
+            }
+            self.check_fuses(&node, FUSE_CANNOT_TRANSFER)?;
+            if !self.valid_new_owner(&node, new_owner) {
+                return Err(Error::InvalidOwner);
+            }
+
+            self._transfer(node, new_owner);
+
+            Ok(())
+        }
+
+        /// Only the owner can lock the domain against transfers or release the lock
+        #[ink(message)]
+        pub fn set_lock(&mut self, domain: Vec<u8>, locked: bool) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+
+            if locked {
+                self.locked.insert(node, &true);
+            } else {
+                self.locked.remove(node);
+            }
+            self.touch(node);
+            self.env().emit_event(LockChanged { node, locked });
+
+            Ok(())
+        }
+
+        /// Only the owner can rule out any new subdomains of the domain
+        ///
+        /// One-way: there is no unfreeze, existing subdomains can rely on having no new
+        /// siblings for as long as the domain is registered.
+        #[ink(message)]
+        pub fn freeze_subdomains(&mut self, domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+
+            self.subdomains_frozen.insert(node, &true);
+            self.touch(node);
+            self.env().emit_event(SubdomainsFrozen { node });
+
+            Ok(())
+        }
+
+        /// Only the owner can burn `FUSE_*` bits of the domain
+        ///
+        /// Burned fuses can never be restored, not even by the owner; they are only
+        /// dropped when the name lapses and is registered again.
+        #[ink(message)]
+        pub fn burn_fuses(&mut self, domain: Vec<u8>, fuses: u32) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+
+            let fuses = self.fuses.get(node).unwrap_or_default() | fuses;
+            self.fuses.insert(node, &fuses);
+            self.touch(node);
+            self.env().emit_event(FusesBurned { node, fuses });
+
+            Ok(())
+        }
+
+        /// `FUSE_*` bits burned for the domain
+        #[ink(message)]
+        pub fn fuses(&self, domain: Vec<u8>) -> u32 {
+            let node = self.get_node(domain);
+            self.fuses.get(node).unwrap_or_default()
+        }
+
+        /// Whether the owner froze the subdomains of the domain
+        #[ink(message)]
+        pub fn subdomains_frozen(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            self.subdomains_frozen.contains(node)
+        }
+
+        /// Only the owner can delegate record management of the domain to a controller
+        ///
+        /// The controller can update resolvers and records but cannot transfer the name.
+        /// Setting the owner itself as controller removes the controller.
+        #[ink(message)]
+        pub fn set_controller(
+            &mut self,
+            domain: Vec<u8>,
+            controller: AccountId,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if self.expired(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+
+            self.touch(node);
+            if controller == self.env().caller() {
+                self.controllers.remove(node);
+                self.env().emit_event(ControllerChanged {
+                    node,
+                    controller: None,
+                });
+            } else {
+                self.controllers.insert(node, &controller);
+                self.env().emit_event(ControllerChanged {
+                    node,
+                    controller: Some(controller),
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Controller of the domain, if any
+        #[ink(message)]
+        pub fn controller(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            self.controllers.get(node)
+        }
+
+        /// Whether `who` may manage the records of the domain
+        ///
+        /// True for the owner, operators of the owner and the controller of a live name.
+        #[ink(message)]
+        pub fn is_authorized(&self, domain: Vec<u8>, who: AccountId) -> bool {
+            let node = self.get_node(domain);
+            self.manages_records_for(&node, who)
+        }
+
+        /// Whether the domain is locked against transfers
+        #[ink(message)]
+        pub fn is_locked(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            self.locked.contains(node)
+        }
+
+        /// Extend the registration of a domain by `additional_secs` seconds.
+        ///
+        /// The owner may renew after expiry as long as the grace period has not passed.
+        /// The transferred value must cover the `cost` of the extension, any excess is refunded.
+        #[ink(message, payable)]
+        pub fn renew_domain(&mut self, domain: Vec<u8>, additional_secs: u64) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let domain = Self::normalize(&domain);
+            let node = self.get_node(domain.clone());
+            let expiry = match self.expiries.get(node) {
+                Some(expiry) => expiry,
+                None => return Err(Error::NameNotExists),
+            };
+            if self.released(&node) {
+                return Err(Error::DomainExpired);
+            }
+            if !self.is_owner(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            if additional_secs < self.min_duration_secs {
+                return Err(Error::DurationTooShort);
+            }
+            let new_expiry = Self::add_secs(expiry, additional_secs)?;
+            // The cap applies to the term left after renewal, not to each extension.
+            let term = new_expiry.saturating_sub(self.env().block_timestamp());
+            if self.max_duration_secs > 0 && term > self.max_duration_secs.saturating_mul(1000) {
+                return Err(Error::DurationTooLong);
+            }
+            let price = self.cost_of(&domain, additional_secs)?;
+            let transferred = self.env().transferred_balance();
+            if transferred < price {
+                return Err(Error::InsufficientFee);
+            }
+
+            if transferred > price {
+                self.env()
+                    .transfer(self.env().caller(), transferred - price)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            self.expiries.insert(node, &new_expiry);
+            self.touch(node);
+            self.env().emit_event(Renew { node, new_expiry });
+
+            Ok(())
+        }
+
+        /// Previous owner re-registers an expired domain during its grace period.
+        ///
+        /// The owner record of an expired name is kept until the name is registered again,
+        /// so it still names the previous owner. Unlike `renew_domain` the standard `cost` is
+        /// charged and the new term of `duration_secs` seconds starts now; other records
+        /// are kept. Anyone else, and any call outside the grace period, gets `InGracePeriod`.
+        #[ink(message, payable)]
+        pub fn reclaim(&mut self, domain: Vec<u8>, duration_secs: u64) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let domain = Self::normalize(&domain);
+            let node = self.get_node(domain.clone());
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            let caller = self.env().caller();
+            if !self.in_grace_period(&node) || self.records.get(node) != Some(caller) {
+                return Err(Error::InGracePeriod);
+            }
+            self.check_duration(duration_secs)?;
+            let price = self.cost_of(&domain, duration_secs)?;
+            let transferred = self.env().transferred_balance();
+            if transferred < price {
+                return Err(Error::InsufficientFee);
+            }
+            let new_expiry = Self::add_secs(self.env().block_timestamp(), duration_secs)?;
+
+            if transferred > price {
+                self.env()
+                    .transfer(caller, transferred - price)
+                    .map_err(|_| Error::TransferFailed)?;
+            }
+            self.expiries.insert(node, &new_expiry);
+            self.touch(node);
+            self.env().emit_event(Renew { node, new_expiry });
+
+            Ok(())
+        }
+
+        /// Manager sets the expiry of a domain directly, e.g. for premium terms or migrations
+        #[ink(message)]
+        pub fn set_expiry(&mut self, domain: Vec<u8>, expiry: Timestamp) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let caller = self.env().caller();
+            if caller != self.manager {
+                return Err(Error::UnauthorizedCaller);
+            };
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+
+            self.expiries.insert(node, &expiry);
+            self.touch(node);
+            self.env().emit_event(Renew {
+                node,
+                new_expiry: expiry,
+            });
+
+            Ok(())
+        }
+
+        /// Whether the domain has expired but can still be renewed or reclaimed by its owner
+        #[ink(message)]
+        pub fn is_in_grace_period(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            self.in_grace_period(&node)
+        }
+
+        /// Whether the registration of the domain has passed its expiry
+        #[ink(message)]
+        pub fn is_expired(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            self.expired(&node)
+        }
+
+        /// `is_expired` for several domains, in the order they were given
+        ///
+        /// Only the first `MAX_BATCH_SIZE` domains are looked up, the result is cut off after them.
+        #[ink(message)]
+        pub fn expired_among(&self, domains: Vec<Vec<u8>>) -> Vec<bool> {
+            domains
+                .into_iter()
+                .take(MAX_BATCH_SIZE)
+                .map(|domain| self.is_expired(domain))
+                .collect()
+        }
+
+        /// Time the domain was registered, or transferred to its current owner
+        #[ink(message)]
+        pub fn registered_at(&self, domain: Vec<u8>) -> Option<Timestamp> {
+            let node = self.get_node(domain);
+            self.registered_at.get(node)
+        }
+
+        /// Block in which the domain was last registered, transferred or had its records changed
+        #[ink(message)]
+        pub fn last_modified(&self, domain: Vec<u8>) -> Option<BlockNumber> {
+            let node = self.get_node(domain);
+            self.last_modified.get(node)
+        }
+
+        /// Expiry timestamp of the domain registration
+        #[ink(message)]
+        pub fn domain_expiry(&self, domain: Vec<u8>) -> Option<Timestamp> {
+            let node = self.get_node(domain);
+            self.expiries.get(node)
+        }
+
+        /// Whether the domain can be registered right now
+        ///
+        /// False for invalid or reserved names, live registrations and names in their grace period,
+        /// and for every name while the contract is paused.
+        #[ink(message)]
+        pub fn is_available(&self, domain: Vec<u8>) -> bool {
+            !self.paused && self.check_registrable(&domain).is_ok()
+        }
+
+        /// Node exist or note
+        #[ink(message)]
+        pub fn is_domain_exist(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            if self.records.contains(node) {
+                true
+            } else {
+                false
+            }
+        }
+
+        /// `is_domain_exist` for several domains, in the order they were given
+        ///
+        /// Only the first `MAX_BATCH_SIZE` domains are looked up, the result is cut off after them.
+        #[ink(message)]
+        pub fn exist_among(&self, domains: Vec<Vec<u8>>) -> Vec<bool> {
+            domains
+                .into_iter()
+                .take(MAX_BATCH_SIZE)
+                .map(|domain| self.records.contains(self.get_node(domain)))
+                .collect()
+        }
+
+        /// renounce ownership by manager
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self ,  domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let caller = self.env().caller();
+            let  node = self.get_node(domain);
+            if caller != self.manager {
+                return Err(Error::UnauthorizedCaller);
+            };       
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+           self._remove_record(node);
+           Ok(())
+        }
+
+
+        /// Manager reassigns a name, e.g. to settle a trademark dispute
+        ///
+        /// Unlike `transfer_domain_ownership` this needs no authorization by the owner
+        /// and ignores the transfer lock, only the manager can call it.
+        #[ink(message)]
+        pub fn admin_transfer(
+            &mut self,
+            domain: Vec<u8>,
+            new_owner: AccountId,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let caller = self.env().caller();
+            if caller != self.manager {
+                return Err(Error::UnauthorizedCaller);
+            };
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+            if !self.valid_new_owner(&node, new_owner) {
+                return Err(Error::InvalidOwner);
+            }
+
+            self._transfer(node, new_owner);
+
+            Ok(())
+        }
+
+        /// renounce ownership by oner only
+        #[ink(message)]
+        pub fn renounce_my_ownership(&mut self ,  domain: Vec<u8>) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let  node = self.get_node(domain);
+            if !self.authorized(&node) {
+                return Err(Error::UnauthorizedCaller);
+            };       
+            if !self.records.contains(node) {
+                return Err(Error::NameNotExists);
+            }
+           self._remove_record(node);
+           Ok(())
+        }
+
+        /// Owner of the parent name reclaims a subdomain, whoever owns it
+        #[ink(message)]
+        pub fn revoke_subdomain(
+            &mut self,
+            domain: Vec<u8>,
+            subdomain: Vec<u8>,
+        ) -> Result<(), Error> {
+            if self.paused {
+                return Err(Error::Paused);
+            }
+            let node = self.get_node(domain.clone());
+            if !self.authorized(&node) {
+                return Err(Error::UnauthorizedCaller);
+            }
+            let subnode = self.get_subnode(domain, subdomain);
+            if !self.records.contains(subnode) {
+                return Err(Error::NameNotExists);
+            }
+
+            self._remove_record(subnode);
+            self.total_subdomains -= 1;
+            if let Some(mut subnodes) = self.subnodes.get(node) {
+                subnodes.retain(|existing| *existing != subnode);
+                self.subnodes.insert(node, &subnodes);
+            }
+
+            Ok(())
+        }
+
+        /// Readable name of a node
+        #[ink(message)]
+        pub fn label_of(&self, node: [u8; 32]) -> Option<Vec<u8>> {
+            self.labels.get(node)
+        }
+
+        /// Number of registered names, subdomains included
+        #[ink(message)]
+        pub fn total_names(&self) -> u32 {
+            self.total_names
+        }
+
+        /// Number of registered subdomains
+        #[ink(message)]
+        pub fn total_subdomains(&self) -> u32 {
+            self.total_subdomains
+        }
+
+        /// Subnodes issued under the domain, their names are available through `label_of`
+        #[ink(message)]
+        pub fn subdomains_of(&self, domain: Vec<u8>) -> Vec<[u8; 32]> {
+            let node = self.get_node(domain);
+            self.subnodes.get(node).unwrap_or_default()
+        }
+
+        /// Nodes owned by the account
+        #[ink(message)]
+        pub fn domains_of(&self, owner: AccountId) -> Vec<[u8; 32]> {
+            self.owned.get(owner).unwrap_or_default()
+        }
+
+        /// Up to `limit` nodes owned by the account, starting at index `start`
+        ///
+        /// `limit` is clamped to `MAX_PAGE_SIZE`, see `domain_count_of` for the total.
+        #[ink(message)]
+        pub fn domains_of_paged(&self, owner: AccountId, start: u32, limit: u32) -> Vec<[u8; 32]> {
+            let limit = limit.min(MAX_PAGE_SIZE) as usize;
+            self.domains_of(owner)
+                .into_iter()
+                .skip(start as usize)
+                .take(limit)
+                .collect()
+        }
+
+        /// Registered node at `index` of the enumeration, see `node_count`
+        ///
+        /// Removing a name moves the last node into its index.
+        #[ink(message)]
+        pub fn node_at(&self, index: u32) -> Option<[u8; 32]> {
+            self.node_keys.get(index)
+        }
+
+        /// Number of registered nodes, names and subdomains
+        #[ink(message)]
+        pub fn node_count(&self) -> u32 {
+            self.node_count
+        }
+
+        /// Owner and resolver of a node, for seeding a successor contract
+        ///
+        /// Only the admin can export.
+        #[ink(message)]
+        pub fn export_node(
+            &self,
+            node: [u8; 32],
+        ) -> Result<Option<(AccountId, Option<Resolver>)>, Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            }
+            Ok(self
+                .records
+                .get(node)
+                .map(|owner| (owner, self.resolvers.get(node))))
+        }
+
+        /// `export_node` for up to `limit` registered nodes, starting at index `start`
+        ///
+        /// `limit` is clamped to `MAX_PAGE_SIZE`. Removing a name moves the last node into
+        /// its index, so a migration should run while registrations are paused.
+        #[ink(message)]
+        pub fn export_nodes(&self, start: u32, limit: u32) -> Result<Vec<NodeExport>, Error> {
+            if self.env().caller() != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            }
+            let end = start
+                .saturating_add(limit.min(MAX_PAGE_SIZE))
+                .min(self.node_count);
+            Ok((start..end)
+                .filter_map(|position| self.node_keys.get(position))
+                .filter_map(|node| {
+                    self.records
+                        .get(node)
+                        .map(|owner| (node, owner, self.resolvers.get(node)))
+                })
+                .collect())
+        }
+
+        /// Number of nodes owned by the account
+        #[ink(message)]
+        pub fn domain_count_of(&self, owner: AccountId) -> u32 {
+            self.domains_of(owner).len() as u32
+        }
+
+        /// SubNode exist or note
+        #[ink(message)]
+        pub fn is_subdomain_exist(&self, domain: Vec<u8>, subdomain: Vec<u8>) -> bool {
+            let subnode = self.get_subnode(domain, subdomain);
+            if self.records.contains(subnode) {
+                true
+            } else {
+                false
+            }
+        }
+
+        fn _set_owner(&mut self, node: [u8; 32], owner: AccountId) -> bool {
+            // let node = self.get_node(domain);
+            let previous_owner = self.records.get(node);
+            if let Some(previous_owner) = previous_owner {
+                self.remove_owned(previous_owner, &node);
+            }
+            self.records.insert(node, &owner);
+            if RESET_REGISTERED_AT_ON_TRANSFER || !self.registered_at.contains(node) {
+                self.registered_at.insert(node, &self.env().block_timestamp());
+            }
+            let mut nodes = self.owned.get(owner).unwrap_or_default();
+            nodes.push(node);
+            self.owned.insert(owner, &nodes);
+            // Approvals, controllers and staged transfers never survive a change of owner.
+            self.approvals.remove(node);
+            self.controllers.remove(node);
+            self.pending_transfers.remove(node);
+            self.touch(node);
+            if previous_owner.is_none() {
+                self._index_node(node);
+                self.env().emit_event(NewOwner { node, owner });
+            }
+
+            return true;
+        }
+
+        /// Hands an existing name to `new_owner` and emits `Transfer`.
+        fn _transfer(&mut self, node: [u8; 32], new_owner: AccountId) {
+            self._set_owner(node, new_owner);
+            self.env().emit_event(Transfer {
+                node,
+                owner: new_owner,
+                label: self.labels.get(node).unwrap_or_default(),
+            });
+        }
+
+        /// Appends the node to `node_keys` unless it is indexed already.
+        fn _index_node(&mut self, node: [u8; 32]) {
+            if self.node_positions.contains(node) {
+                return;
+            }
+            self.node_keys.insert(self.node_count, &node);
+            self.node_positions.insert(node, &self.node_count);
+            self.node_count += 1;
+        }
+
+        /// Drops the node from `node_keys`, moving the last node into its position.
+        fn _unindex_node(&mut self, node: [u8; 32]) {
+            let position = match self.node_positions.get(node) {
+                Some(position) => position,
+                None => return,
+            };
+            let last = self.node_count - 1;
+            if position != last {
+                if let Some(moved) = self.node_keys.get(last) {
+                    self.node_keys.insert(position, &moved);
+                    self.node_positions.insert(moved, &position);
+                }
+            }
+            self.node_keys.remove(last);
+            self.node_positions.remove(node);
+            self.node_count = last;
+        }
+
+        /// Appends the node to `reserved_keys` unless it is listed already.
+        fn _index_reserved(&mut self, node: [u8; 32]) {
+            if self.reserved_positions.contains(node) {
+                return;
+            }
+            self.reserved_keys.insert(self.reserved_count, &node);
+            self.reserved_positions.insert(node, &self.reserved_count);
+            self.reserved_count += 1;
+        }
+
+        /// Drops the node from `reserved_keys`, moving the last node into its position.
+        fn _unindex_reserved(&mut self, node: [u8; 32]) {
+            let position = match self.reserved_positions.get(node) {
+                Some(position) => position,
+                None => return,
+            };
+            let last = self.reserved_count - 1;
+            if position != last {
+                if let Some(moved) = self.reserved_keys.get(last) {
+                    self.reserved_keys.insert(position, &moved);
+                    self.reserved_positions.insert(moved, &position);
+                }
+            }
+            self.reserved_keys.remove(last);
+            self.reserved_positions.remove(node);
+            self.reserved_count = last;
+        }
+
+        /// Records the current block as the last change of the node.
+        fn touch(&mut self, node: [u8; 32]) {
+            self.last_modified.insert(node, &self.env().block_number());
+        }
+
+        fn _remove_record(&mut self, node: [u8; 32]) {
+            if let Some(previous_owner) = self.records.get(node) {
+                self.records.remove(node);
+                self.remove_owned(previous_owner, &node);
+                self._unindex_node(node);
+                self.total_names -= 1;
+                self.touch(node);
+                self.env().emit_event(Renounce {
+                    node,
+                    previous_owner,
+                });
+            }
+            self._clear_node(node);
+        }
+
+        /// Drops everything stored for the node except its owner.
+        fn _clear_node(&mut self, node: [u8; 32]) {
+            self.expiries.remove(node);
+            self.labels.remove(node);
+            self.approvals.remove(node);
+            self.resolvers.remove(node);
+            self.resolver_history.remove(node);
+            self.record_kinds.remove(node);
+            self.content_hashes.remove(node);
+            self.ipv4s.remove(node);
+            self.ipv6s.remove(node);
+            self.ttls.remove(node);
+            self.locked.remove(node);
+            self.subdomains_frozen.remove(node);
+            self.fuses.remove(node);
+            self.controllers.remove(node);
+            self.registered_at.remove(node);
+            self.pending_transfers.remove(node);
+            self._clear_texts_and_addresses(&node);
+        }
+
+        /// Text and address records are keyed by `(node, _)`, their keys are tracked per node.
+        fn _clear_texts_and_addresses(&mut self, node: &[u8; 32]) {
+            for key in self.text_keys.get(node).unwrap_or_default() {
+                self.texts.remove(&(*node, key));
+            }
+            self.text_keys.remove(node);
+            for coin_type in self.coin_types.get(node).unwrap_or_default() {
+                self.addresses.remove((*node, coin_type));
+            }
+            self.coin_types.remove(node);
+        }
+
+        fn remove_owned(&mut self, owner: AccountId, node: &[u8; 32]) {
+            if let Some(mut nodes) = self.owned.get(owner) {
+                nodes.retain(|owned| owned != node);
+                if nodes.is_empty() {
+                    self.owned.remove(owner);
+                } else {
+                    self.owned.insert(owner, &nodes);
+                }
+            }
+        }
+
+        fn _set_resolver(&mut self, node: [u8; 32], resolver: Resolver) {
+            // let node = self.get_node(domain);
+
+            let old_resolver = self.resolvers.get(node);
+            self.resolvers.insert(node, &resolver);
+            let mut history = self.resolver_history.get(node).unwrap_or_default();
+            if history.len() >= MAX_RESOLVER_HISTORY {
+                history.remove(0);
+            }
+            history.push((resolver, self.env().block_number()));
+            self.resolver_history.insert(node, &history);
+            self.touch(node);
+            self.env().emit_event(NewResolver {
+                node,
+                resolver,
+                old_resolver,
+            });
+        }
+
+        /// Current manager of contract
+        #[ink(message)]
+        pub fn current_manager(&self) -> AccountId {
+            self.manager
+        }
+
+        /// Admin of the contract
+        #[ink(message)]
+        pub fn admin(&self) -> AccountId {
+            self.admin
+        }
+
+        /// Admin proposed by the current admin, if any
+        #[ink(message)]
+        pub fn pending_admin(&self) -> Option<AccountId> {
+            self.pending_admin
+        }
+
+        /// Admin and manager with their proposed successors in one call
+        #[ink(message)]
+        pub fn governance(&self) -> Governance {
+            Governance {
+                admin: self.admin,
+                manager: self.manager,
+                pending_admin: self.pending_admin,
+                pending_manager: self.pending_manager,
+            }
+        }
+
+        /// Only Admin can propose a new admin, who has to accept the role
+        #[ink(message)]
+        pub fn transfer_admin(&mut self, new_admin: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.pending_admin = Some(new_admin);
+
+            Ok(())
+        }
+
+        /// Only the pending admin can accept the admin role
+        #[ink(message)]
+        pub fn accept_admin(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if Some(caller) != self.pending_admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            let old = self.admin;
+            self.admin = caller;
+            self.pending_admin = None;
+
+            self.env().emit_event(AdminTransferred { old, new: caller });
+
+            Ok(())
+        }
+
+        /// Only Admin can give up the admin role for good
+        ///
+        /// IRREVERSIBLE: the admin becomes the zero account and any pending admin is dropped,
+        /// so no one can ever call an admin only message again. Fees can no longer be
+        /// withdrawn and pausing, pricing, reservations and the manager are fixed forever.
+        #[ink(message)]
+        pub fn renounce_admin(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            let zero = AccountId::from([0u8; 32]);
+            self.admin = zero;
+            self.pending_admin = None;
+
+            self.env().emit_event(AdminTransferred {
+                old: caller,
+                new: zero,
+            });
+
+            Ok(())
+        }
+
+        /// Whether state changing operations are paused
+        #[ink(message)]
+        pub fn paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Only Admin can pause state changing operations, queries keep working
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.paused = true;
+            self.env().emit_event(Paused { admin: caller });
+
+            Ok(())
+        }
+
+        /// Only Admin can resume state changing operations
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.paused = false;
+            self.env().emit_event(Unpaused { admin: caller });
+
+            Ok(())
+        }
+
+        /// Whether the name is reserved
+        #[ink(message)]
+        pub fn is_reserved(&self, domain: Vec<u8>) -> bool {
+            let node = self.get_node(domain);
+            self.reserved.contains(node)
+        }
+
+        /// Nodes of all reserved names
+        ///
+        /// Releasing a reservation moves the last reserved node into its position.
+        #[ink(message)]
+        pub fn reserved_names(&self) -> Vec<[u8; 32]> {
+            (0..self.reserved_count)
+                .filter_map(|position| self.reserved_keys.get(position))
+                .collect()
+        }
+
+        /// Only Admin can reserve a name or release a reservation
+        #[ink(message)]
+        pub fn reserve_name(&mut self, domain: Vec<u8>, reserved: bool) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            let node = self.get_node(domain);
+            if reserved {
+                self.reserved.insert(node, &true);
+                self._index_reserved(node);
+            } else {
+                self.reserved.remove(node);
+                self._unindex_reserved(node);
+            }
+
+            Ok(())
+        }
+
+        /// Fee charged for registering a name
+        #[ink(message)]
+        pub fn registration_fee(&self) -> Balance {
+            self.registration_fee
+        }
+
+        /// Price of registering the name, see `price_of`
+        #[ink(message)]
+        pub fn price_for(&self, domain: Vec<u8>) -> Balance {
+            self.price_of(&domain)
+        }
+
+        /// Amount charged for registering the name for `duration_secs` seconds
+        ///
+        /// Quotes the same amount `register_domain` enforces, `Balance::MAX` if it overflows.
+        #[ink(message)]
+        pub fn cost(&self, domain: Vec<u8>, duration_secs: u64) -> Balance {
+            self.cost_of(&domain, duration_secs).unwrap_or(Balance::MAX)
+        }
+
+        /// Only Admin can set the price for names of `len` bytes
+        #[ink(message)]
+        pub fn set_length_price(&mut self, len: u32, price: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.length_prices.insert(len, &price);
+            if self.max_priced_len < Some(len) {
+                self.max_priced_len = Some(len);
+            }
+
+            Ok(())
+        }
+
+        /// Price for a name of the given length, per `PRICE_PERIOD_SECS`.
+        ///
+        /// Uses the price configured for the exact length. Names longer than the
+        /// highest configured length are priced at that highest tier, anything else
+        /// falls back to the registration fee.
+        fn price_of(&self, domain: &[u8]) -> Balance {
+            let len = domain.len() as u32;
+            if let Some(price) = self.length_prices.get(len) {
+                return price;
+            }
+            match self.max_priced_len {
+                Some(top) if len > top => self.length_prices.get(top).unwrap_or_default(),
+                _ => self.registration_fee,
+            }
+        }
+
+        /// Price of the name for every started `PRICE_PERIOD_SECS`, at least one period.
+        fn cost_of(&self, domain: &[u8], duration_secs: u64) -> Result<Balance, Error> {
+            let mut periods = duration_secs / PRICE_PERIOD_SECS;
+            if !duration_secs.is_multiple_of(PRICE_PERIOD_SECS) || periods == 0 {
+                periods += 1;
+            }
+            self.price_of(domain)
+                .checked_mul(periods as Balance)
+                .ok_or(Error::ArithmeticOverflow)
+        }
+
+        /// Share of a registration price credited to the referrer, in basis points
+        #[ink(message)]
+        pub fn referral_bps(&self) -> u16 {
+            self.referral_bps
+        }
+
+        /// Only Admin can set the referral share, at most 10000 basis points
+        #[ink(message)]
+        pub fn set_referral_bps(&mut self, bps: u16) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+            if bps > 10_000 {
+                return Err(Error::InvalidRate);
+            }
+
+            self.referral_bps = bps;
+
+            Ok(())
+        }
+
+        /// Subdomains a parent may have at once, zero means unlimited
+        #[ink(message)]
+        pub fn max_subdomains(&self) -> u32 {
+            self.max_subdomains
+        }
+
+        /// Only Admin can limit the subdomains of a parent, zero lifts the limit
+        #[ink(message)]
+        pub fn set_max_subdomains(&mut self, max_subdomains: u32) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.max_subdomains = max_subdomains;
+
+            Ok(())
+        }
+
+        /// Layout version the storage was last migrated to
+        #[ink(message)]
+        pub fn storage_version(&self) -> u16 {
+            self.storage_version
+        }
+
+        /// Only Admin can bring the storage of an upgraded contract to `STORAGE_VERSION`
+        ///
+        /// Initializes the fields added since the stored version, a no-op once migrated.
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            // Version 1 is the first versioned layout, its new fields start zeroed.
+            if self.storage_version < STORAGE_VERSION {
+                self.storage_version = STORAGE_VERSION;
+            }
+
+            Ok(())
+        }
+
+        /// Window length in blocks and registrations allowed per window
+        #[ink(message)]
+        pub fn registration_limit(&self) -> (BlockNumber, u32) {
+            (self.registration_window_blocks, self.max_registrations_per_window)
+        }
+
+        /// Only Admin can rate limit registrations, a `max` of zero lifts the limit
+        #[ink(message)]
+        pub fn set_registration_limit(
+            &mut self,
+            window_blocks: BlockNumber,
+            max: u32,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.registration_window_blocks = window_blocks;
+            self.max_registrations_per_window = max;
+
+            Ok(())
+        }
+
+        /// Whether names may point to the resolver, always true while the allowlist is disabled
+        #[ink(message)]
+        pub fn is_resolver_allowed(&self, resolver: Resolver) -> bool {
+            !self.allowlist_enabled || self.resolver_allowlist.contains(resolver)
+        }
+
+        /// Only Admin can add a resolver to or remove it from the allowlist
+        #[ink(message)]
+        pub fn set_resolver_allowed(
+            &mut self,
+            resolver: Resolver,
+            allowed: bool,
+        ) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            if allowed {
+                self.resolver_allowlist.insert(resolver, &true);
+            } else {
+                self.resolver_allowlist.remove(resolver);
+            }
+
+            Ok(())
+        }
+
+        /// Whether resolvers are restricted to the allowlist
+        #[ink(message)]
+        pub fn allowlist_enabled(&self) -> bool {
+            self.allowlist_enabled
+        }
+
+        /// Only Admin can restrict resolvers to the allowlist or lift the restriction
+        #[ink(message)]
+        pub fn set_allowlist_enabled(&mut self, enabled: bool) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.allowlist_enabled = enabled;
+
+            Ok(())
+        }
+
+        /// Shortest and longest registration or renewal in seconds
+        #[ink(message)]
+        pub fn duration_limits(&self) -> (u64, u64) {
+            (self.min_duration_secs, self.max_duration_secs)
+        }
+
+        /// Only Admin can limit registration and renewal periods, a `max` of zero lifts the cap
+        #[ink(message)]
+        pub fn set_duration_limits(&mut self, min: u64, max: u64) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.min_duration_secs = min;
+            self.max_duration_secs = max;
+
+            Ok(())
+        }
+
+        /// Resolver of registrations passing the zero account as resolver, if any
+        #[ink(message)]
+        pub fn default_resolver(&self) -> Option<Resolver> {
+            self.default_resolver
+        }
+
+        /// Only Admin can set or clear the default resolver of new registrations
+        #[ink(message)]
+        pub fn set_default_resolver(&mut self, resolver: Option<Resolver>) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.default_resolver = resolver;
+
+            Ok(())
+        }
+
+        /// Only Admin can change the registration fee
+        #[ink(message)]
+        pub fn set_registration_fee(&mut self, fee: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.registration_fee = fee;
+
+            Ok(())
+        }
+
+        /// Only Admin can withdraw collected fees from the contract
+        #[ink(message)]
+        pub fn withdraw(&mut self, to: AccountId, amount: Balance) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+            if amount > self.env().balance().saturating_sub(self.total_owed) {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.env()
+                .transfer(to, amount)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(Withdrawal { to, amount });
+
+            Ok(())
+        }
+
+        /// Funds the account can withdraw through `withdraw_balance`
+        #[ink(message)]
+        pub fn balance_of(&self, who: AccountId) -> Balance {
+            self.balances.get(who).unwrap_or_default()
+        }
+
+        /// Pull the funds credited to the caller
+        ///
+        /// The ledger entry is cleared before the transfer and restored if it fails.
+        #[ink(message)]
+        pub fn withdraw_balance(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            let amount = self.balance_of(caller);
+            if amount == 0 {
+                return Err(Error::InsufficientBalance);
+            }
+
+            self.balances.remove(caller);
+            self.total_owed -= amount;
+            if self.env().transfer(caller, amount).is_err() {
+                self.balances.insert(caller, &amount);
+                self.total_owed += amount;
+                return Err(Error::TransferFailed);
+            }
+            self.env().emit_event(Withdrawal { to: caller, amount });
+
+            Ok(())
+        }
+
+        /// Credits `amount` to the withdrawable balance of `who`.
+        fn _credit(&mut self, who: AccountId, amount: Balance) -> Result<(), Error> {
+            let balance = self
+                .balance_of(who)
+                .checked_add(amount)
+                .ok_or(Error::ArithmeticOverflow)?;
+            let total_owed = self
+                .total_owed
+                .checked_add(amount)
+                .ok_or(Error::ArithmeticOverflow)?;
+            self.balances.insert(who, &balance);
+            self.total_owed = total_owed;
+            Ok(())
+        }
+
+        /// Only Admin can propose a new manager, who has to accept the role
+        #[ink(message)]
+        pub fn change_manager(&mut self, _manager: AccountId) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            self.pending_manager = Some(_manager);
+
+            Ok(())
+        }
+
+        /// Manager proposed by the admin, if any
+        #[ink(message)]
+        pub fn pending_manager(&self) -> Option<AccountId> {
+            self.pending_manager
+        }
+
+        /// Only the pending manager can accept the manager role
+        #[ink(message)]
+        pub fn accept_manager(&mut self) -> Result<(), Error> {
+            let caller = self.env().caller();
+            if Some(caller) != self.pending_manager {
+                return Err(Error::UnauthorizedCaller);
+            };
+
+            let old = self.manager;
+            self.manager = caller;
+            self.pending_manager = None;
+
+            self.env().emit_event(ChangeManager {
+                _current_manager: Some(old),
+                _new_manager: Some(caller),
+            });
+
+            Ok(())
+        }
+
+        /// Query the node of a domain, does not change any state
+        #[ink(message)]
+        pub fn compute_node(&self, domain: Vec<u8>) -> [u8; 32] {
+            self.get_node(domain)
+        }
+
+        /// calculate node from lable
+        ///
+        /// Blake2x256 of `NODE_TAG` and the SCALE encoded label after `normalize`, clients can
+        /// compute it locally.
+        #[inline]
+        pub fn get_node(&self, domain: Vec<u8>) -> [u8; 32] {
+            let encodable = (NODE_TAG, Self::normalize(&domain)); // Implements `scale::Encode`
+            let mut output = <Blake2x256 as HashOutput>::Type::default(); // 256-bit buffer
+            ink_env::hash_encoded::<Blake2x256, _>(&encodable, &mut output);
+            output
+        }
+
+        /// calculate subnode from lable
+        ///
+        /// The subnode is derived from the parent node, see `subnode_from_parent`.
+        #[inline]
+        pub fn get_subnode(&self, domain: Vec<u8>, subdomain: Vec<u8>) -> [u8; 32] {
+            let parent_node = self.get_node(domain);
+            self.subnode_from_parent(parent_node, subdomain)
+        }
+
+        /// Node of a path of labels, top level name first
+        ///
+        /// `["a", "b", "c"]` is the node of `c.b.a`, each label is hashed under the node
+        /// of the labels before it. An empty path is the zero node.
+        #[ink(message)]
+        pub fn node_for_path(&self, labels: Vec<Vec<u8>>) -> [u8; 32] {
+            let mut labels = labels.into_iter();
+            let top = match labels.next() {
+                Some(top) => self.get_node(top),
+                None => return [0u8; 32],
+            };
+            labels.fold(top, |parent, label| self.subnode_from_parent(parent, label))
+        }
+
+        /// Resolver of the most specific name along a path that has one
+        ///
+        /// Labels are in DNS order, most specific first unlike `node_for_path`, so
+        /// `["x", "y", "z"]` tries `x.y.z`, then `y.z`, then `z`.
+        #[ink(message)]
+        pub fn resolve_with_fallback(&self, labels: Vec<Vec<u8>>) -> Option<Resolver> {
+            let mut nodes: Vec<[u8; 32]> = Vec::new();
+            for label in labels.into_iter().rev() {
+                let node = match nodes.last() {
+                    Some(parent) => self.subnode_from_parent(*parent, label),
+                    None => self.get_node(label),
+                };
+                nodes.push(node);
+            }
+            nodes
+                .iter()
+                .rev()
+                .find_map(|node| self.resolvers.get(node))
+        }
+
+        /// calculate subnode from the parent node
+        ///
+        /// Blake2x256 of `SUBNODE_TAG ++ parent_node ++ get_node(subdomain)`, like the ENS
+        /// namehash.
+        #[inline]
+        pub fn subnode_from_parent(&self, parent_node: [u8; 32], subdomain: Vec<u8>) -> [u8; 32] {
+            // Implements `scale::Encode`
+            let encodable = (SUBNODE_TAG, parent_node, self.get_node(subdomain));
+            let mut output = <Blake2x256 as HashOutput>::Type::default(); // 256-bit buffer
+            ink_env::hash_encoded::<Blake2x256, _>(&encodable, &mut output);
+            output
+        }
+
+        /// Owner of the domain, `None` once the registration expired and its grace period passed
+        ///
+        /// An expired name in its grace period still reports the owner, who may renew or
+        /// `reclaim` it until the grace period ends.
+        #[ink(message)]
+        pub fn owner(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            self.owner_by_node(node)
+            //self.token_approvals.get(&id).cloned()
+        }
+
+        /// `owner` looked up by a node computed off-chain
+        #[ink(message)]
+        pub fn owner_by_node(&self, node: [u8; 32]) -> Option<AccountId> {
+            if self.released(&node) {
+                return None;
+            }
+            self.records.get(node)
+        }
+
+        /// Stored owner of the domain regardless of expiry, for admin and debugging use
+        #[ink(message)]
+        pub fn raw_owner(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            self.records.get(node)
+        }
+
+        //    /// Returns the approved account ID for this token if any.
+        //    #[ink(message)]
+        //    pub fn get_approved(&self, id: TokenId) -> Option<AccountId> {
+        //        self.token_approvals.get(&id).cloned()
+        //    }
+
+        /// Owner, resolver, expiry and record kind of the domain in one read
+        #[ink(message)]
+        pub fn record(
+            &self,
+            domain: Vec<u8>,
+        ) -> Option<(AccountId, Option<Resolver>, Option<Timestamp>, u8)> {
+            let node = self.get_node(domain);
+            let owner = self.records.get(node)?;
+            Some((
+                owner,
+                self.resolvers.get(node),
+                self.expiries.get(node),
+                self.record_kinds.get(node).unwrap_or(RECORD_KIND_WALLET),
+            ))
+        }
+
+        /// Owner, resolver, expiry, label and lock of the domain, `None` if not registered
+        #[ink(message)]
+        pub fn domain_info(&self, domain: Vec<u8>) -> Option<DomainInfo> {
+            let node = self.get_node(domain);
+            let owner = self.records.get(node)?;
+            Some(DomainInfo {
+                owner,
+                resolver: self.resolvers.get(node),
+                expiry: self.expiries.get(node),
+                label: self.labels.get(node),
+                locked: self.locked.contains(node),
+            })
+        }
+
+        /// Kinds of records set on the domain, all unset for unregistered names
+        #[ink(message)]
+        pub fn records_present(&self, domain: Vec<u8>) -> RecordFlags {
+            let node = self.get_node(domain);
+            if !self.records.contains(node) {
+                return RecordFlags::default();
+            }
+            RecordFlags {
+                has_resolver: self.resolvers.contains(node),
+                has_content_hash: self.content_hashes.contains(node),
+                has_any_text: !self.text_keys.get(node).unwrap_or_default().is_empty(),
+                has_any_addr: !self.coin_types.get(node).unwrap_or_default().is_empty(),
+            }
+        }
+
+        #[ink(message)]
+        pub fn domain_resolver(&self, domain: Vec<u8>) -> Option<Resolver> {
+            let node = self.get_node(domain);
+            return self.resolvers.get(node);
+        }
+
+        /// `domain_resolver` looked up by a node computed off-chain
+        #[ink(message)]
+        pub fn resolver_by_node(&self, node: [u8; 32]) -> Option<Resolver> {
+            self.resolvers.get(node)
+        }
+
+        /// Recent resolvers of the domain with the block each was set in, oldest first
+        #[ink(message)]
+        pub fn resolver_history(&self, domain: Vec<u8>) -> Vec<(Resolver, BlockNumber)> {
+            let node = self.get_node(domain);
+            self.resolver_history.get(node).unwrap_or_default()
+        }
+
+        /// Account the domain resolves to through its resolver contract
+        ///
+        /// Calls `AddrResolver::addr` on the resolver. If the resolver is not a contract
+        /// or the call fails the resolver itself is returned as the address.
+        #[ink(message)]
+        pub fn resolve_addr(&self, domain: Vec<u8>) -> Option<AccountId> {
+            let node = self.get_node(domain);
+            let resolver = self.resolvers.get(node)?;
+            build_call::<Environment>()
+                .callee(resolver)
+                .exec_input(
+                    ExecutionInput::new(Selector::new(ink_lang::selector_bytes!(
+                        "AddrResolver::addr"
+                    )))
+                    .push_arg(node),
+                )
+                .returns::<ReturnType<Option<AccountId>>>()
+                .fire()
+                .unwrap_or(Some(resolver))
+        }
+
+        /// `domain_resolver` for several domains, in the order they were given
+        ///
+        /// Only the first `MAX_BATCH_SIZE` domains are looked up, the result is cut off after them.
+        #[ink(message)]
+        pub fn resolvers_of(&self, domains: Vec<Vec<u8>>) -> Vec<Option<Resolver>> {
+            domains
+                .into_iter()
+                .take(MAX_BATCH_SIZE)
+                .map(|domain| self.resolvers.get(self.get_node(domain)))
+                .collect()
+        }
+
         #[ink(message)]
         pub fn subdomain_resolver(&self, domain: Vec<u8>, subdomain: Vec<u8>) -> Option<Resolver> {
             let node = self.get_subnode(domain, subdomain);
-            return self.resolvers.get(&node).cloned();
+            return self.resolvers.get(node);
+        }
+    }
+
+    /// Unit tests
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use ink_lang as ink;
+
+        fn default_accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
+            ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("Cannot get accounts")
+        }
+
+        type Event = <PeerName as ::ink_lang::reflect::ContractEventBase>::Type;
+
+        /// Grace period used by the test contracts.
+        const GRACE_SECS: u64 = 1;
+
+        /// Secret used for the registration commitments of the tests.
+        const SECRET: [u8; 32] = [0x2A; 32];
+
+        /// Deploy a test contract, without the value the default context transfers.
+        ///
+        /// The contract starts with no balance, so refunding that value would fail.
+        fn new_contract(manager: AccountId) -> PeerName {
+            set_caller(ink_env::caller::<ink_env::DefaultEnvironment>());
+            PeerName::default(manager, manager, GRACE_SECS, 1, 63, 0, 60)
+        }
+
+        /// Commit to and register `domain` in one go.
+        fn register(
+            contract: &mut PeerName,
+            domain: Vec<u8>,
+            owner: AccountId,
+            resolver: Resolver,
+            duration_secs: u64,
+        ) -> Result<(), Error> {
+            let commitment = contract.make_commitment(domain.clone(), owner, SECRET);
+            let _ = contract.commit(commitment);
+            contract.register_domain(
+                domain,
+                owner,
+                resolver,
+                duration_secs,
+                SECRET,
+                false,
+                None,
+                Vec::new(),
+            )
+        }
+
+        /// Push a new execution context with `caller` as the caller.
+        fn set_caller(caller: AccountId) {
+            set_caller_with_value(caller, 0);
+        }
+
+        /// Push a new execution context with `caller` transferring `value`.
+        fn set_caller_with_value(caller: AccountId, value: Balance) {
+            let callee = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            let data = ink_env::test::CallData::new(ink_env::call::Selector::new([0x00; 4]));
+            ink_env::test::push_execution_context::<ink_env::DefaultEnvironment>(
+                caller, callee, 1000000, value, data,
+            );
+        }
+
+        /// Advance the chain until the block timestamp passes `timestamp`.
+        fn advance_past(timestamp: Timestamp) {
+            while ink_env::block_timestamp::<ink_env::DefaultEnvironment>() <= timestamp {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+        }
+
+        #[ink::test]
+        fn new_makes_deployer_admin_and_manager() {
+            let accounts = default_accounts();
+            set_caller(accounts.bob);
+            let contract = PeerName::new();
+            assert_eq!(contract.admin(), accounts.bob);
+            assert_eq!(contract.current_manager(), accounts.bob);
+        }
+
+        #[ink::test]
+        fn migrate_bumps_storage_version() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            // Storage written before versioning reads as version zero.
+            contract.storage_version = 0;
+            set_caller(accounts.bob);
+            assert_eq!(contract.migrate(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.alice);
+            assert_eq!(contract.migrate(), Ok(()));
+            assert_eq!(contract.storage_version(), STORAGE_VERSION);
+            assert_eq!(contract.migrate(), Ok(()));
+        }
+
+        #[ink::test]
+        fn node_hash_is_32_bytes() {
+            let accounts = default_accounts();
+            let contract = new_contract(accounts.alice);
+            assert_eq!(core::mem::size_of::<<Blake2x256 as HashOutput>::Type>(), 32);
+            assert_eq!(contract.get_node(b"alice".to_vec()).len(), 32);
+        }
+
+        #[ink::test]
+        fn names_are_case_insensitive() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                contract.get_node(b"MyName".to_vec()),
+                contract.get_node(b"myname".to_vec())
+            );
+            assert_eq!(
+                register(&mut contract, b"MyName".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"myname".to_vec()), Some(accounts.bob));
+            assert_eq!(
+                contract.label_of(contract.get_node(b"myname".to_vec())),
+                Some(b"myname".to_vec())
+            );
+            assert_eq!(
+                register(&mut contract, b"myname".to_vec(), accounts.eve, accounts.eve, 1),
+                Err(Error::NameAlreadyExists)
+            );
+        }
+
+        #[ink::test]
+        fn subnode_derives_from_parent() {
+            let accounts = default_accounts();
+            let contract = new_contract(accounts.alice);
+            let parent = contract.get_node(b"alice".to_vec());
+            assert_eq!(
+                contract.get_subnode(b"alice".to_vec(), b"pay".to_vec()),
+                contract.subnode_from_parent(parent, b"pay".to_vec())
+            );
+            assert_ne!(
+                contract.get_subnode(b"alice".to_vec(), b"pay".to_vec()),
+                contract.get_subnode(b"bob".to_vec(), b"pay".to_vec())
+            );
+        }
+
+        #[ink::test]
+        fn nodes_and_subnodes_cannot_collide() {
+            let contract = new_contract(default_accounts().alice);
+            // A 63 byte label encodes with the length prefix 0xFC, so without the tags it
+            // hashes the same bytes as a parent node starting with 0xFC and a child node.
+            let mut parent = [0u8; 32];
+            parent[0] = 0xFC;
+            let child = contract.get_node(b"pay".to_vec());
+            let mut label = parent[1..].to_vec();
+            label.extend_from_slice(&child);
+            assert_eq!(
+                scale::Encode::encode(&label),
+                scale::Encode::encode(&(parent, child))
+            );
+            assert_ne!(
+                contract.get_node(label),
+                contract.subnode_from_parent(parent, b"pay".to_vec())
+            );
+        }
+
+        #[ink::test]
+        fn path_nodes_fold_labels() {
+            let accounts = default_accounts();
+            let contract = new_contract(accounts.alice);
+            assert_eq!(
+                contract.node_for_path(vec![b"alice".to_vec()]),
+                contract.get_node(b"alice".to_vec())
+            );
+            assert_eq!(
+                contract.node_for_path(vec![b"alice".to_vec(), b"pay".to_vec()]),
+                contract.get_subnode(b"alice".to_vec(), b"pay".to_vec())
+            );
+            let pay = contract.get_subnode(b"alice".to_vec(), b"pay".to_vec());
+            assert_eq!(
+                contract.node_for_path(vec![b"alice".to_vec(), b"pay".to_vec(), b"eu".to_vec()]),
+                contract.subnode_from_parent(pay, b"eu".to_vec())
+            );
+        }
+
+        #[ink::test]
+        fn resolution_falls_back_to_parents() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.bob,
+                    accounts.django
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                contract.resolve_with_fallback(vec![b"pay".to_vec(), b"alice".to_vec()]),
+                Some(accounts.django)
+            );
+            assert_eq!(
+                contract.resolve_with_fallback(vec![
+                    b"eu".to_vec(),
+                    b"pay".to_vec(),
+                    b"alice".to_vec()
+                ]),
+                Some(accounts.django)
+            );
+            assert_eq!(
+                contract.resolve_with_fallback(vec![b"mail".to_vec(), b"alice".to_vec()]),
+                Some(accounts.charlie)
+            );
+            assert_eq!(
+                contract.resolve_with_fallback(vec![b"pay".to_vec(), b"nobody".to_vec()]),
+                None
+            );
+            assert_eq!(contract.resolve_with_fallback(Vec::new()), None);
+        }
+
+        #[ink::test]
+        fn label_length_is_validated() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, Vec::new(), accounts.bob, accounts.bob, 1),
+                Err(Error::InvalidName)
+            );
+            assert_eq!(
+                register(&mut contract, [b'a'; 64].to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::InvalidName)
+            );
+            assert_eq!(
+                register(&mut contract, b"a".to_vec(), accounts.alice, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(b"a".to_vec(), Vec::new(), accounts.alice, accounts.bob),
+                Err(Error::InvalidName)
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"a".to_vec(),
+                    [b'b'; 64].to_vec(),
+                    accounts.alice,
+                    accounts.bob
+                ),
+                Err(Error::InvalidName)
+            );
+            assert_eq!(
+                contract.set_sub_domain(b"a".to_vec(), b"b".to_vec(), accounts.alice, accounts.bob),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn label_characters_are_validated() {
+            assert!(PeerName::is_valid_label(b"my-name"));
+            assert!(PeerName::is_valid_label(b"name42"));
+            assert!(!PeerName::is_valid_label(b"-bad"));
+            assert!(!PeerName::is_valid_label(b"bad-"));
+            assert!(!PeerName::is_valid_label(b"Bad"));
+            assert!(!PeerName::is_valid_label(b"my name"));
+            assert!(!PeerName::is_valid_label(b"nul\0byte"));
+
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"-bad".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::InvalidName)
+            );
+            assert_eq!(
+                register(&mut contract, b"my-name".to_vec(), accounts.alice, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"my-name".to_vec(),
+                    b"nul\0byte".to_vec(),
+                    accounts.alice,
+                    accounts.bob
+                ),
+                Err(Error::InvalidName)
+            );
+        }
+
+        #[ink::test]
+        fn registration_requires_commitment() {
+            let accounts = default_accounts();
+            set_caller(accounts.alice);
+            let mut contract = PeerName::default(
+                accounts.alice,
+                accounts.alice,
+                GRACE_SECS,
+                1,
+                63,
+                1,
+                2,
+            );
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::CommitmentNotFound)
+            );
+            let commitment = contract.make_commitment(b"bob".to_vec(), accounts.bob, SECRET);
+            assert_eq!(contract.commit(commitment), Ok(()));
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::CommitmentTooNew)
+            );
+            advance_past(1000);
+            // A different secret does not match the commitment.
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    [0; 32],
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::CommitmentNotFound)
+            );
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Ok(())
+            );
+            // The commitment has been consumed.
+            assert_eq!(contract.commitments.get(commitment), None);
+        }
+
+        #[ink::test]
+        fn overwrite_updates_existing_registration() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.eve,
+                    accounts.eve,
+                    5,
+                    SECRET,
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::NameAlreadyExists)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.eve,
+                    accounts.eve,
+                    5,
+                    SECRET,
+                    true,
+                    None,
+                    Vec::new()
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(1000));
+            assert_eq!(contract.total_names(), 1);
+        }
+
+        #[ink::test]
+        fn register_with_initial_texts() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let commitment = contract.make_commitment(b"bob".to_vec(), accounts.bob, SECRET);
+            assert_eq!(contract.commit(commitment), Ok(()));
+            let texts = vec![
+                (b"email".to_vec(), b"bob@example.com".to_vec()),
+                (b"url".to_vec(), b"https://bob.example".to_vec()),
+            ];
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    vec![(b"k".to_vec(), b"v".to_vec()); MAX_INITIAL_TEXTS + 1]
+                ),
+                Err(Error::BatchTooLarge)
+            );
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    texts
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                contract.get_text(b"bob".to_vec(), b"email".to_vec()),
+                Some(b"bob@example.com".to_vec())
+            );
+            assert_eq!(
+                contract.get_text(b"bob".to_vec(), b"url".to_vec()),
+                Some(b"https://bob.example".to_vec())
+            );
+            let changed = ink_env::test::recorded_events()
+                .filter(|event| {
+                    matches!(
+                        <Event as scale::Decode>::decode(&mut &event.data[..]),
+                        Ok(Event::TextChanged(_))
+                    )
+                })
+                .count();
+            assert_eq!(changed, 2);
+        }
+
+        #[ink::test]
+        fn stale_commitment_is_rejected() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let commitment = contract.make_commitment(b"bob".to_vec(), accounts.bob, SECRET);
+            assert_eq!(contract.commit(commitment), Ok(()));
+            advance_past(60 * 1000);
+            assert_eq!(
+                contract.register_domain(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    SECRET,
+                    false,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::CommitmentNotFound)
+            );
+        }
+
+        #[ink::test]
+        fn register_sets_expiry() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(contract.domain_expiry(b"alice".to_vec()), Some(1000));
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn registration_time_restarts_on_transfer() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.registered_at(b"bob".to_vec()), None);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.registered_at(b"bob".to_vec()), Some(0));
+
+            advance_past(10);
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Ok(accounts.bob)
+            );
+            assert_eq!(contract.registered_at(b"bob".to_vec()), Some(now));
+        }
+
+        #[ink::test]
+        fn owner_is_none_once_released() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+            advance_past(1000);
+            assert!(contract.is_in_grace_period(b"bob".to_vec()));
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+            advance_past(1000 + GRACE_SECS * 1000);
+            assert_eq!(contract.owner(b"bob".to_vec()), None);
+            assert_eq!(contract.raw_owner(b"bob".to_vec()), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn lookups_by_node_match_lookups_by_label() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            let node = contract.compute_node(b"bob".to_vec());
+            assert_eq!(contract.owner_by_node(node), Some(accounts.bob));
+            assert_eq!(contract.resolver_by_node(node), Some(accounts.charlie));
+            let unknown = contract.compute_node(b"nobody".to_vec());
+            assert_eq!(contract.owner_by_node(unknown), None);
+            assert_eq!(contract.resolver_by_node(unknown), None);
+        }
+
+        #[ink::test]
+        fn manager_can_set_expiry() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                contract.set_expiry(b"bob".to_vec(), 5000),
+                Err(Error::NameNotExists)
+            );
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.set_expiry(b"bob".to_vec(), 5000), Ok(()));
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(5000));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_expiry(b"bob".to_vec(), u64::MAX),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn last_modified_tracks_changes() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.last_modified(b"bob".to_vec()), None);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            let registered = contract.last_modified(b"bob".to_vec());
+            assert!(registered.is_some());
+
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.last_modified(b"bob".to_vec()),
+                Some(ink_env::block_number::<ink_env::DefaultEnvironment>())
+            );
+            assert_ne!(contract.last_modified(b"bob".to_vec()), registered);
+        }
+
+        #[ink::test]
+        fn expiry_can_be_queried_in_bulk() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 10),
+                Ok(())
+            );
+            advance_past(1000);
+            assert!(contract.is_expired(b"alice".to_vec()));
+            assert!(!contract.is_expired(b"bob".to_vec()));
+            assert_eq!(
+                contract.expired_among(vec![
+                    b"alice".to_vec(),
+                    b"bob".to_vec(),
+                    b"nobody".to_vec()
+                ]),
+                vec![true, false, false]
+            );
+            let expired = contract.expired_among(vec![b"alice".to_vec(); MAX_BATCH_SIZE + 1]);
+            assert_eq!(expired, vec![true; MAX_BATCH_SIZE]);
+        }
+
+        #[ink::test]
+        fn exist_among_checks_each_domain() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.exist_among(vec![b"nobody".to_vec(), b"bob".to_vec()]),
+                vec![false, true]
+            );
+            assert_eq!(contract.exist_among(Vec::new()), Vec::<bool>::new());
+            let exist = contract.exist_among(vec![b"bob".to_vec(); MAX_BATCH_SIZE + 1]);
+            assert_eq!(exist, vec![true; MAX_BATCH_SIZE]);
+        }
+
+        #[ink::test]
+        fn events_carry_labels() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Ok(accounts.bob)
+            );
+            let labels: Vec<Vec<u8>> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::Register(event)) => Some(event.label),
+                    Ok(Event::Transfer(event)) => Some(event.label),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(labels, vec![b"bob".to_vec(), b"bob".to_vec()]);
+        }
+
+        #[ink::test]
+        fn new_owner_fires_on_creation_and_transfer_on_change() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Ok(accounts.bob)
+            );
+            // Re-registering the lapsed name hands it to a new owner as well.
+            advance_past(1000 + GRACE_SECS * 1000);
+            set_caller(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(contract.domains_of(accounts.eve), Vec::<[u8; 32]>::new());
+            let owners: Vec<(&str, AccountId)> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::NewOwner(event)) => Some(("new", event.owner)),
+                    Ok(Event::Transfer(event)) => Some(("transfer", event.owner)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                owners,
+                vec![
+                    ("new", accounts.bob),
+                    ("transfer", accounts.eve),
+                    ("new", accounts.charlie)
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn register_event_names_the_registrar() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            let registrations: Vec<(AccountId, AccountId)> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::Register(event)) => Some((event.from, event.registrar)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(registrations, vec![(accounts.bob, accounts.alice)]);
+        }
+
+        #[ink::test]
+        fn register_event_carries_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.bob,
+                    accounts.django
+                ),
+                Ok(())
+            );
+            let resolvers: Vec<Option<Resolver>> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::Register(event)) => Some(event.resolver),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(resolvers, vec![Some(accounts.charlie), Some(accounts.django)]);
+        }
+
+        #[ink::test]
+        fn resolver_events_carry_old_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.django),
+                Ok(())
+            );
+            let changes: Vec<(Resolver, Option<Resolver>)> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::NewResolver(event)) => Some((event.resolver, event.old_resolver)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(
+                changes,
+                vec![
+                    (accounts.bob, None),
+                    (accounts.charlie, Some(accounts.bob)),
+                    (accounts.django, Some(accounts.charlie)),
+                ]
+            );
+        }
+
+        #[ink::test]
+        fn resolver_allowlist_restricts_resolvers_when_enabled() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            // Disabled by default, any resolver is accepted.
+            assert!(!contract.allowlist_enabled());
+            assert!(contract.is_resolver_allowed(accounts.django));
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.django, 1),
+                Ok(())
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_allowlist_enabled(true),
+                Err(Error::UnauthorizedCaller)
+            );
+            assert_eq!(
+                contract.set_resolver_allowed(accounts.charlie, true),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_allowlist_enabled(true), Ok(()));
+            assert_eq!(contract.set_resolver_allowed(accounts.charlie, true), Ok(()));
+            assert!(!contract.is_resolver_allowed(accounts.django));
+
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::ResolverNotAllowed)
+            );
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.eve),
+                Err(Error::ResolverNotAllowed)
+            );
+            assert_eq!(
+                contract.set_addr(b"alice".to_vec(), NATIVE_COIN_TYPE, vec![0x05; 32]),
+                Err(Error::ResolverNotAllowed)
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.alice,
+                    accounts.eve
+                ),
+                Err(Error::ResolverNotAllowed)
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.charlie),
+                Ok(())
+            );
+
+            assert_eq!(contract.set_resolver_allowed(accounts.charlie, false), Ok(()));
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.charlie),
+                Err(Error::ResolverNotAllowed)
+            );
+        }
+
+        #[ink::test]
+        fn resolver_history_keeps_recent_changes() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                .expect("Cannot advance block");
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.resolver_history(b"bob".to_vec()),
+                vec![(accounts.bob, 0), (accounts.charlie, 1)]
+            );
+
+            // Only the last `MAX_RESOLVER_HISTORY` changes are kept.
+            for _ in 0..MAX_RESOLVER_HISTORY {
+                assert_eq!(
+                    contract.update_domain_resolver(b"bob".to_vec(), accounts.django),
+                    Ok(())
+                );
+            }
+            let history = contract.resolver_history(b"bob".to_vec());
+            assert_eq!(history.len(), MAX_RESOLVER_HISTORY);
+            assert!(history.iter().all(|(resolver, _)| *resolver == accounts.django));
+        }
+
+        #[ink::test]
+        fn resolver_is_set_only_if_absent() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.clear_records(b"bob".to_vec()), Ok(()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_resolver_if_absent(b"bob".to_vec(), accounts.charlie),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_resolver_if_absent(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_resolver_if_absent(b"bob".to_vec(), accounts.django),
+                Err(Error::ResolverAlreadySet)
+            );
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn expired_owner_loses_control() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            advance_past(1000);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.django),
+                Err(Error::DomainExpired)
+            );
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.django, false),
+                Err(Error::DomainExpired)
+            );
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn renew_extends_expiry() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            advance_past(1000);
+            // Only the owner can renew.
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 1),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.renew_domain(b"bob".to_vec(), 1), Ok(()));
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(2000));
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.renew_domain(b"carol".to_vec(), 1),
+                Err(Error::NameNotExists)
+            );
+        }
+
+        #[ink::test]
+        fn durations_are_limited() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_duration_limits(10, 100), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_duration_limits(10, 100), Ok(()));
+            assert_eq!(contract.duration_limits(), (10, 100));
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 9),
+                Err(Error::DurationTooShort)
+            );
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 101),
+                Err(Error::DurationTooLong)
+            );
+            assert_eq!(
+                contract.batch_register(vec![b"eve".to_vec()], accounts.eve, accounts.eve, 101),
+                Err(Error::DurationTooLong)
+            );
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 10),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"eve".to_vec(), accounts.eve, accounts.eve, 100),
+                Ok(())
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 9),
+                Err(Error::DurationTooShort)
+            );
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 101),
+                Err(Error::DurationTooLong)
+            );
+            // 10 seconds are left, so at most 90 more fit under the cap.
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 91),
+                Err(Error::DurationTooLong)
+            );
+            assert_eq!(contract.renew_domain(b"bob".to_vec(), 90), Ok(()));
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(100 * 1000));
+            // Repeated renewals cannot stack terms past the cap either.
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 10),
+                Err(Error::DurationTooLong)
+            );
+        }
+
+        #[ink::test]
+        fn overflowing_duration_is_rejected() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, u64::MAX),
+                Err(Error::ArithmeticOverflow)
+            );
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), None);
+            assert_eq!(
+                contract.batch_register(
+                    vec![b"carol".to_vec()],
+                    accounts.bob,
+                    accounts.bob,
+                    u64::MAX
+                ),
+                Err(Error::ArithmeticOverflow)
+            );
+
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), u64::MAX),
+                Err(Error::ArithmeticOverflow)
+            );
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(1000));
+        }
+
+        #[ink::test]
+        fn grace_period_protects_owner() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert!(!contract.is_in_grace_period(b"bob".to_vec()));
+            advance_past(1000);
+            assert!(contract.is_in_grace_period(b"bob".to_vec()));
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.charlie, 1),
+                Err(Error::InGracePeriod)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.renew_domain(b"bob".to_vec(), 1), Ok(()));
+            assert!(!contract.is_in_grace_period(b"bob".to_vec()));
+        }
+
+        #[ink::test]
+        fn renew_fails_after_grace_period() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            advance_past(1000 + GRACE_SECS * 1000);
+            assert!(!contract.is_in_grace_period(b"bob".to_vec()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), 1),
+                Err(Error::DomainExpired)
+            );
+        }
+
+        #[ink::test]
+        fn only_previous_owner_reclaims_in_grace_period() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            // Not expired yet, `renew_domain` is the way to extend a live name.
+            assert_eq!(contract.reclaim(b"bob".to_vec(), 5), Err(Error::InGracePeriod));
+
+            advance_past(1000);
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            set_caller_with_value(accounts.charlie, 100);
+            assert_eq!(contract.reclaim(b"bob".to_vec(), 5), Err(Error::InGracePeriod));
+            set_caller_with_value(accounts.bob, 99);
+            assert_eq!(contract.reclaim(b"bob".to_vec(), 5), Err(Error::InsufficientFee));
+            set_caller_with_value(accounts.bob, 100);
+            assert_eq!(contract.reclaim(b"bob".to_vec(), 5), Ok(()));
+            let now = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+            assert_eq!(contract.domain_expiry(b"bob".to_vec()), Some(now + 5000));
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+
+            advance_past(now + 5000 + GRACE_SECS * 1000);
+            assert_eq!(contract.reclaim(b"bob".to_vec(), 5), Err(Error::InGracePeriod));
+        }
+
+        #[ink::test]
+        fn expired_domain_can_be_reclaimed() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            // A live name cannot be taken.
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.charlie, 1),
+                Err(Error::NameAlreadyExists)
+            );
+            advance_past(1000 + GRACE_SECS * 1000);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.django, 1),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.charlie));
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn lapsed_records_do_not_carry_over() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"bob.io".to_vec()),
+                Ok(())
+            );
+            assert_eq!(contract.set_addr(b"bob".to_vec(), 60, vec![0xAB; 20]), Ok(()));
+            assert_eq!(contract.set_content_hash(b"bob".to_vec(), vec![0x01; 34]), Ok(()));
+            assert_eq!(contract.set_ipv4(b"bob".to_vec(), [10, 0, 0, 1]), Ok(()));
+            assert_eq!(contract.set_ttl(b"bob".to_vec(), 300), Ok(()));
+            assert_eq!(contract.set_record_kind(b"bob".to_vec(), RECORD_KIND_IPFS), Ok(()));
+
+            advance_past(1000 + GRACE_SECS * 1000);
+            set_caller(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(contract.get_text(b"bob".to_vec(), b"url".to_vec()), None);
+            assert_eq!(contract.addr(b"bob".to_vec(), 60), None);
+            assert_eq!(contract.content_hash(b"bob".to_vec()), None);
+            assert_eq!(contract.ipv4(b"bob".to_vec()), None);
+            assert_eq!(contract.ttl(b"bob".to_vec()), 0);
+            assert_eq!(contract.record_kind(b"bob".to_vec()), RECORD_KIND_WALLET);
+            let history = contract.resolver_history(b"bob".to_vec());
+            assert_eq!(history.len(), 1);
+            assert_eq!(history[0].0, accounts.charlie);
+        }
+
+        #[ink::test]
+        fn registration_requires_fee() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            assert_eq!(contract.registration_fee(), 100);
+            set_caller_with_value(accounts.alice, 99);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::InsufficientFee)
+            );
+            set_caller_with_value(accounts.alice, 100);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            // Only the admin can change the fee.
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_registration_fee(0), Err(Error::UnauthorizedCaller));
+        }
+
+        #[ink::test]
+        fn price_depends_on_length() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(10), Ok(()));
+            assert_eq!(contract.set_length_price(3, 300), Ok(()));
+            assert_eq!(contract.set_length_price(5, 50), Ok(()));
+            assert_eq!(contract.price_for(b"ab".to_vec()), 10);
+            assert_eq!(contract.price_for(b"abc".to_vec()), 300);
+            assert_eq!(contract.price_for(b"abcd".to_vec()), 10);
+            assert_eq!(contract.price_for(b"abcde".to_vec()), 50);
+            assert_eq!(contract.price_for([b'a'; 40].to_vec()), 50);
+            set_caller_with_value(accounts.alice, 299);
+            assert_eq!(
+                register(&mut contract, b"abc".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::InsufficientFee)
+            );
+            set_caller_with_value(accounts.alice, 300);
+            assert_eq!(
+                register(&mut contract, b"abc".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn cost_matches_enforced_amount() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_length_price(3, 300), Ok(()));
+            assert_eq!(contract.cost(b"abc".to_vec(), 0), 300);
+            assert_eq!(contract.cost(b"abc".to_vec(), PRICE_PERIOD_SECS), 300);
+            let quote = contract.cost(b"abc".to_vec(), PRICE_PERIOD_SECS + 1);
+            assert_eq!(quote, 600);
+            set_caller_with_value(accounts.alice, quote - 1);
+            assert_eq!(
+                register(
+                    &mut contract,
+                    b"abc".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    PRICE_PERIOD_SECS + 1
+                ),
+                Err(Error::InsufficientFee)
+            );
+            set_caller_with_value(accounts.alice, quote);
+            assert_eq!(
+                register(
+                    &mut contract,
+                    b"abc".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    PRICE_PERIOD_SECS + 1
+                ),
+                Ok(())
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_length_price(4, Balance::MAX), Ok(()));
+            assert_eq!(contract.cost(b"abcd".to_vec(), PRICE_PERIOD_SECS * 2), Balance::MAX);
+        }
+
+        #[ink::test]
+        fn renewal_is_charged_per_period() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            let quote = contract.cost(b"bob".to_vec(), PRICE_PERIOD_SECS * 10);
+            assert_eq!(quote, 1000);
+            set_caller_with_value(accounts.bob, quote - 1);
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), PRICE_PERIOD_SECS * 10),
+                Err(Error::InsufficientFee)
+            );
+            set_caller_with_value(accounts.bob, quote);
+            assert_eq!(
+                contract.renew_domain(b"bob".to_vec(), PRICE_PERIOD_SECS * 10),
+                Ok(())
+            );
+            assert_eq!(
+                contract.domain_expiry(b"bob".to_vec()),
+                Some(1000 + PRICE_PERIOD_SECS * 10 * 1000)
+            );
+        }
+
+        #[ink::test]
+        fn overpayment_is_refunded() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            // Emulate alice paying 2x the fee into the contract.
+            let contract_id = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.alice, 800)
+                .expect("Cannot set caller balance");
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_id, 200)
+                .expect("Cannot set contract balance");
+            set_caller_with_value(accounts.alice, 200);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice),
+                Ok(900)
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract_id),
+                Ok(100)
+            );
+        }
+
+        #[ink::test]
+        fn batch_overpayment_is_refunded() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            let contract_id = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(accounts.alice, 500)
+                .expect("Cannot set caller balance");
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_id, 250)
+                .expect("Cannot set contract balance");
+            set_caller_with_value(accounts.alice, 250);
+            assert_eq!(
+                contract.batch_register(
+                    vec![b"bob".to_vec(), b"eve".to_vec()],
+                    accounts.bob,
+                    accounts.bob,
+                    1
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.alice),
+                Ok(550)
+            );
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(contract_id),
+                Ok(200)
+            );
+        }
+
+        #[ink::test]
+        fn failed_refund_aborts_registration() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(100), Ok(()));
+            // The contract holds nothing, so the refund cannot be paid out.
+            set_caller_with_value(accounts.alice, 200);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::TransferFailed)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn text_records_work() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Ok(())
+            );
+            assert_eq!(
+                contract.get_text(b"bob".to_vec(), b"url".to_vec()),
+                Some(b"https://bob".to_vec())
+            );
+            assert_eq!(contract.get_text(b"bob".to_vec(), b"email".to_vec()), None);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.get_text(b"bob".to_vec(), b"url".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn text_keys_are_listed_once() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            for (key, value) in [
+                (b"url".to_vec(), b"https://bob".to_vec()),
+                (b"email".to_vec(), b"bob@example.com".to_vec()),
+                (b"url".to_vec(), b"https://bob.example".to_vec()),
+            ] {
+                assert_eq!(contract.set_text(b"bob".to_vec(), key, value), Ok(()));
+            }
+            assert_eq!(
+                contract.text_keys(b"bob".to_vec()),
+                vec![b"url".to_vec(), b"email".to_vec()]
+            );
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), Vec::new()),
+                Ok(())
+            );
+            assert_eq!(contract.text_keys(b"bob".to_vec()), vec![b"email".to_vec()]);
+        }
+
+        #[ink::test]
+        fn address_records_work() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            let eth = vec![0xAB; 20];
+            assert_eq!(contract.set_addr(b"bob".to_vec(), 60, eth.clone()), Ok(()));
+            assert_eq!(contract.addr(b"bob".to_vec(), 60), Some(eth));
+            assert_eq!(contract.addr(b"bob".to_vec(), 0), None);
+            // The native coin type maps onto the resolver.
+            assert_eq!(
+                contract.addr(b"bob".to_vec(), NATIVE_COIN_TYPE),
+                Some(vec![0x02; 32])
+            );
+            assert_eq!(
+                contract.set_addr(b"bob".to_vec(), NATIVE_COIN_TYPE, vec![0x03; 32]),
+                Ok(())
+            );
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.charlie));
+            assert_eq!(
+                contract.set_addr(b"bob".to_vec(), NATIVE_COIN_TYPE, vec![0x03; 20]),
+                Err(Error::InvalidAddress)
+            );
+        }
+
+        #[ink::test]
+        fn content_hash_works() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_content_hash(b"bob".to_vec(), b"ipfs://cid".to_vec()),
+                Ok(())
+            );
+            assert_eq!(contract.content_hash(b"bob".to_vec()), Some(b"ipfs://cid".to_vec()));
+            assert_eq!(contract.set_content_hash(b"bob".to_vec(), Vec::new()), Ok(()));
+            assert_eq!(contract.content_hash(b"bob".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn ip_records_work() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_ipv4(b"bob".to_vec(), [127, 0, 0, 1]),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            let v6 = [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+            assert_eq!(contract.set_ipv4(b"bob".to_vec(), [127, 0, 0, 1]), Ok(()));
+            assert_eq!(contract.set_ipv6(b"bob".to_vec(), v6), Ok(()));
+            assert_eq!(contract.ipv4(b"bob".to_vec()), Some([127, 0, 0, 1]));
+            assert_eq!(contract.ipv6(b"bob".to_vec()), Some(v6));
+            assert_eq!(
+                contract.set_ipv4(b"nobody".to_vec(), [127, 0, 0, 1]),
+                Err(Error::NameNotExists)
+            );
+
+            let changes = ink_env::test::recorded_events()
+                .filter(|event| {
+                    matches!(
+                        <Event as scale::Decode>::decode(&mut &event.data[..]),
+                        Ok(Event::Ipv4Changed(_)) | Ok(Event::Ipv6Changed(_))
+                    )
+                })
+                .count();
+            assert_eq!(changes, 2);
+
+            assert_eq!(contract.clear_records(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.ipv4(b"bob".to_vec()), None);
+            assert_eq!(contract.ipv6(b"bob".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn resolve_addr_without_resolver_is_none() {
+            // Resolving through a contract needs an on-chain environment.
+            let contract = new_contract(default_accounts().alice);
+            assert_eq!(contract.resolve_addr(b"nobody".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn clear_records_keeps_ownership() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.clear_records(b"bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Ok(())
+            );
+            assert_eq!(contract.set_addr(b"bob".to_vec(), 0, b"1BoB".to_vec()), Ok(()));
+            assert_eq!(
+                contract.set_content_hash(b"bob".to_vec(), b"ipfs://cid".to_vec()),
+                Ok(())
+            );
+            assert_eq!(contract.clear_records(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.records_present(b"bob".to_vec()), RecordFlags::default());
+            assert_eq!(contract.get_text(b"bob".to_vec(), b"url".to_vec()), None);
+            assert_eq!(contract.addr(b"bob".to_vec(), 0), None);
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn ttl_works() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.ttl(b"bob".to_vec()), 0);
+            assert_eq!(
+                contract.set_ttl(b"bob".to_vec(), 3600),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_ttl(b"bob".to_vec(), 3600), Ok(()));
+            assert_eq!(contract.ttl(b"bob".to_vec()), 3600);
+        }
+
+        #[ink::test]
+        fn labels_are_tracked() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.alice,
+                    accounts.alice
+                ),
+                Ok(())
+            );
+            let node = contract.get_node(b"alice".to_vec());
+            let subnode = contract.get_subnode(b"alice".to_vec(), b"pay".to_vec());
+            assert_eq!(contract.label_of(node), Some(b"alice".to_vec()));
+            assert_eq!(contract.label_of(subnode), Some(b"pay.alice".to_vec()));
+            assert_eq!(contract.renounce_my_ownership(b"alice".to_vec()), Ok(()));
+            assert_eq!(contract.label_of(node), None);
+        }
+
+        #[ink::test]
+        fn subdomain_can_be_granted_to_another_account() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob
+                ),
+                Ok(())
+            );
+            let subnode = contract.get_subnode(b"alice".to_vec(), b"bob".to_vec());
+            assert_eq!(contract.domains_of(accounts.bob), vec![subnode]);
+            assert_eq!(
+                contract.subdomain_resolver(b"alice".to_vec(), b"bob".to_vec()),
+                Some(accounts.bob)
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"eve".to_vec(),
+                    accounts.bob,
+                    accounts.bob
+                ),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn subdomain_inherits_parent_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"app".to_vec(),
+                    accounts.bob,
+                    AccountId::from([0u8; 32])
+                ),
+                Ok(())
+            );
+            assert_eq!(
+                contract.subdomain_resolver(b"alice".to_vec(), b"app".to_vec()),
+                Some(accounts.charlie)
+            );
+        }
+
+        #[ink::test]
+        fn subdomain_emits_subnode_event() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.alice,
+                    accounts.alice
+                ),
+                Ok(())
+            );
+            let subnode_events: Vec<SubNode> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::SubNode(event)) => Some(event),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(subnode_events.len(), 1);
+            assert_eq!(subnode_events[0].owner, accounts.alice);
+            assert_eq!(subnode_events[0].node, contract.get_node(b"alice".to_vec()));
+            assert_eq!(
+                subnode_events[0].subnode,
+                contract.get_subnode(b"alice".to_vec(), b"pay".to_vec())
+            );
+        }
+
+        #[ink::test]
+        fn approved_transfer_works() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            // Only the owner can approve.
+            assert_eq!(
+                contract.approve(b"bob".to_vec(), accounts.charlie),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.approve(b"bob".to_vec(), accounts.charlie), Ok(()));
+            assert_eq!(contract.get_approved(b"bob".to_vec()), Some(accounts.charlie));
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.transfer_from(b"bob".to_vec(), accounts.eve),
+                Err(Error::NotApproved)
+            );
+            set_caller(accounts.charlie);
+            assert_eq!(contract.transfer_from(b"bob".to_vec(), accounts.django), Ok(()));
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.django));
+            assert_eq!(contract.get_approved(b"bob".to_vec()), None);
+            // The approval was consumed by the transfer.
+            assert_eq!(
+                contract.transfer_from(b"bob".to_vec(), accounts.charlie),
+                Err(Error::NotApproved)
+            );
+        }
+
+        #[ink::test]
+        fn locked_domain_cannot_be_transferred() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.approve(b"bob".to_vec(), accounts.charlie), Ok(()));
+            assert_eq!(contract.set_lock(b"bob".to_vec(), true), Ok(()));
+            assert!(contract.is_locked(b"bob".to_vec()));
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Err(Error::DomainLocked)
+            );
+            assert_eq!(
+                contract.batch_transfer(vec![b"bob".to_vec()], accounts.eve),
+                Err(Error::DomainLocked)
+            );
+
+            // An approved account can neither transfer nor unlock the name.
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.transfer_from(b"bob".to_vec(), accounts.charlie),
+                Err(Error::DomainLocked)
+            );
+            assert_eq!(
+                contract.set_lock(b"bob".to_vec(), false),
+                Err(Error::UnauthorizedCaller)
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_lock(b"bob".to_vec(), false), Ok(()));
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Ok(accounts.bob)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.eve));
+        }
+
+        #[ink::test]
+        fn staged_transfer_freezes_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, true),
+                Ok(accounts.bob)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+            assert_eq!(contract.pending_transfer(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Err(Error::TransferPending)
+            );
+            assert_eq!(
+                contract.set_record(b"bob".to_vec(), accounts.bob, accounts.charlie),
+                Err(Error::TransferPending)
+            );
+            assert_eq!(
+                contract.set_addr(b"bob".to_vec(), NATIVE_COIN_TYPE, vec![0x03; 32]),
+                Err(Error::TransferPending)
+            );
+            assert_eq!(contract.clear_records(b"bob".to_vec()), Err(Error::TransferPending));
+
+            // Only the staged recipient can finalize.
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.accept_transfer(b"bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.eve);
+            assert_eq!(contract.accept_transfer(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(contract.pending_transfer(b"bob".to_vec()), None);
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.bob));
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.eve),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn staged_transfer_can_be_cancelled() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.cancel_transfer(b"bob".to_vec()),
+                Err(Error::NoPendingTransfer)
+            );
+            let events_before = ink_env::test::recorded_events().count();
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, true),
+                Ok(accounts.bob)
+            );
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 1);
+
+            // Only the owner can cancel, not the staged recipient.
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.cancel_transfer(b"bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.cancel_transfer(b"bob".to_vec()), Ok(()));
+            assert_eq!(ink_env::test::recorded_events().count(), events_before + 2);
+            assert_eq!(contract.pending_transfer(b"bob".to_vec()), None);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.accept_transfer(b"bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn transfer_to_self_or_zero_account_is_rejected() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.bob, false),
+                Err(Error::InvalidOwner)
+            );
+            assert_eq!(
+                contract.transfer_domain_ownership(
+                    b"bob".to_vec(),
+                    AccountId::from([0u8; 32]),
+                    false
+                ),
+                Err(Error::InvalidOwner)
+            );
+            assert_eq!(
+                contract.transfer_from(b"bob".to_vec(), AccountId::from([0u8; 32])),
+                Err(Error::InvalidOwner)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+        }
+
+        #[ink::test]
+        fn set_record_updates_owner_and_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_record(b"bob".to_vec(), accounts.alice, accounts.alice),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_record(b"bob".to_vec(), accounts.bob, accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.bob));
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.charlie));
+            assert_eq!(
+                contract.set_record(b"bob".to_vec(), accounts.eve, accounts.eve),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.eve));
+        }
+
+        #[ink::test]
+        fn only_manager_can_force_transfer() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_lock(b"bob".to_vec(), true), Ok(()));
+            assert_eq!(
+                contract.admin_transfer(b"bob".to_vec(), accounts.eve),
+                Err(Error::UnauthorizedCaller)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.admin_transfer(b"bob".to_vec(), accounts.eve), Ok(()));
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(accounts.eve));
+            assert_eq!(
+                contract.admin_transfer(b"nobody".to_vec(), accounts.eve),
+                Err(Error::NameNotExists)
+            );
+        }
+
+        #[ink::test]
+        fn controller_manages_records_but_cannot_transfer() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_controller(b"bob".to_vec(), accounts.charlie), Ok(()));
+            assert_eq!(contract.controller(b"bob".to_vec()), Some(accounts.charlie));
+
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Ok(())
+            );
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.charlie, false),
+                Err(Error::UnauthorizedCaller)
+            );
+            assert_eq!(
+                contract.set_controller(b"bob".to_vec(), accounts.eve),
+                Err(Error::UnauthorizedCaller)
+            );
+
+            // The controller is dropped when the name changes hands.
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.django, false),
+                Ok(accounts.bob)
+            );
+            assert_eq!(contract.controller(b"bob".to_vec()), None);
+        }
+
+        #[ink::test]
+        fn authorization_can_be_queried() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_approval_for_all(accounts.charlie, true), Ok(()));
+            assert_eq!(contract.set_controller(b"bob".to_vec(), accounts.django), Ok(()));
+
+            assert!(contract.is_authorized(b"bob".to_vec(), accounts.bob));
+            assert!(contract.is_authorized(b"bob".to_vec(), accounts.charlie));
+            assert!(contract.is_authorized(b"bob".to_vec(), accounts.django));
+            assert!(!contract.is_authorized(b"bob".to_vec(), accounts.eve));
+            assert!(!contract.is_authorized(b"nobody".to_vec(), accounts.bob));
+            advance_past(1000);
+            assert!(!contract.is_authorized(b"bob".to_vec(), accounts.bob));
+        }
+
+        #[ink::test]
+        fn operator_can_manage_names() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_approval_for_all(accounts.charlie, true), Ok(()));
+            assert!(contract.is_approved_for_all(accounts.bob, accounts.charlie));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.charlie),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_approval_for_all(accounts.charlie, false), Ok(()));
+            assert!(!contract.is_approved_for_all(accounts.bob, accounts.charlie));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.update_domain_resolver(b"bob".to_vec(), accounts.django),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn admin_transfer_takes_two_steps() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.transfer_admin(accounts.bob), Ok(()));
+            assert_eq!(contract.admin(), accounts.alice);
+            assert_eq!(contract.pending_admin(), Some(accounts.bob));
+            set_caller(accounts.charlie);
+            assert_eq!(contract.accept_admin(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_admin(), Ok(()));
+            assert_eq!(contract.admin(), accounts.bob);
+            assert_eq!(contract.pending_admin(), None);
+        }
+
+        #[ink::test]
+        fn renounced_admin_is_gone_for_good() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.transfer_admin(accounts.bob), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(contract.renounce_admin(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.alice);
+            assert_eq!(contract.renounce_admin(), Ok(()));
+            assert_eq!(contract.admin(), AccountId::from([0u8; 32]));
+            assert_eq!(contract.pending_admin(), None);
+            assert_eq!(contract.pause(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_admin(), Err(Error::UnauthorizedCaller));
+        }
+
+        #[ink::test]
+        fn manager_change_takes_two_steps() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.change_manager(accounts.bob),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.change_manager(accounts.bob), Ok(()));
+            assert_eq!(contract.current_manager(), accounts.alice);
+            assert_eq!(contract.pending_manager(), Some(accounts.bob));
+            set_caller(accounts.charlie);
+            assert_eq!(contract.accept_manager(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.bob);
+            assert_eq!(contract.accept_manager(), Ok(()));
+            assert_eq!(contract.current_manager(), accounts.bob);
+            assert_eq!(contract.pending_manager(), None);
+
+            let changes: Vec<_> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::ChangeManager(e)) => Some((e._current_manager, e._new_manager)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(changes, vec![(Some(accounts.alice), Some(accounts.bob))]);
+        }
+
+        #[ink::test]
+        fn governance_reports_pending_roles() {
+            let accounts = default_accounts();
+            let mut contract = PeerName::default(accounts.alice, accounts.bob, 1, 1, 63, 0, 60);
+            assert_eq!(
+                contract.governance(),
+                Governance {
+                    admin: accounts.alice,
+                    manager: accounts.bob,
+                    pending_admin: None,
+                    pending_manager: None,
+                }
+            );
+            assert_eq!(contract.transfer_admin(accounts.charlie), Ok(()));
+            assert_eq!(contract.change_manager(accounts.django), Ok(()));
+            assert_eq!(contract.governance().pending_admin, Some(accounts.charlie));
+            assert_eq!(contract.governance().pending_manager, Some(accounts.django));
+        }
+
+        #[ink::test]
+        fn owned_domains_are_indexed() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"bobby".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            let bob = contract.get_node(b"bob".to_vec());
+            let bobby = contract.get_node(b"bobby".to_vec());
+            assert_eq!(contract.domains_of(accounts.bob), vec![bob, bobby]);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.charlie, false),
+                Ok(accounts.bob)
+            );
+            assert_eq!(contract.domains_of(accounts.bob), vec![bobby]);
+            assert_eq!(contract.domains_of(accounts.charlie), vec![bob]);
+            assert_eq!(contract.renounce_my_ownership(b"bobby".to_vec()), Ok(()));
+            assert_eq!(contract.domains_of(accounts.bob), Vec::<[u8; 32]>::new());
+        }
+
+        #[ink::test]
+        fn owned_domains_can_be_paged() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            for domain in [b"one".to_vec(), b"two".to_vec(), b"three".to_vec()] {
+                assert_eq!(
+                    register(&mut contract, domain, accounts.bob, accounts.bob, 1),
+                    Ok(())
+                );
+            }
+            let nodes = contract.domains_of(accounts.bob);
+            assert_eq!(contract.domain_count_of(accounts.bob), 3);
+            assert_eq!(contract.domains_of_paged(accounts.bob, 0, 2), nodes[..2].to_vec());
+            assert_eq!(contract.domains_of_paged(accounts.bob, 2, 2), nodes[2..].to_vec());
+            assert_eq!(contract.domains_of_paged(accounts.bob, 3, 2), Vec::<[u8; 32]>::new());
+            assert_eq!(contract.domains_of_paged(accounts.bob, 0, u32::MAX), nodes);
+        }
+
+        #[ink::test]
+        fn parent_owner_can_revoke_subdomain() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob
+                ),
+                Ok(())
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.revoke_subdomain(b"alice".to_vec(), b"bob".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+
+            set_caller(accounts.alice);
+            assert_eq!(contract.revoke_subdomain(b"alice".to_vec(), b"bob".to_vec()), Ok(()));
+            assert!(!contract.is_subdomain_exist(b"alice".to_vec(), b"bob".to_vec()));
+            assert_eq!(contract.subdomain_resolver(b"alice".to_vec(), b"bob".to_vec()), None);
+            assert_eq!(contract.domains_of(accounts.bob), Vec::<[u8; 32]>::new());
+            assert_eq!(contract.total_subdomains(), 0);
+            assert_eq!(
+                contract.revoke_subdomain(b"alice".to_vec(), b"bob".to_vec()),
+                Err(Error::NameNotExists)
+            );
+        }
+
+        #[ink::test]
+        fn subdomains_are_limited_per_parent() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_max_subdomains(1), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_max_subdomains(1), Ok(()));
+            assert_eq!(contract.max_subdomains(), 1);
+            let add = |contract: &mut PeerName, subdomain: &[u8]| {
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    subdomain.to_vec(),
+                    accounts.alice,
+                    accounts.alice,
+                )
+            };
+            assert_eq!(add(&mut contract, b"pay"), Ok(()));
+            assert_eq!(add(&mut contract, b"mail"), Err(Error::SubdomainLimitReached));
+
+            // Revoking frees a slot, zero lifts the limit.
+            assert_eq!(contract.revoke_subdomain(b"alice".to_vec(), b"pay".to_vec()), Ok(()));
+            assert_eq!(add(&mut contract, b"mail"), Ok(()));
+            assert_eq!(contract.set_max_subdomains(0), Ok(()));
+            assert_eq!(add(&mut contract, b"pay"), Ok(()));
+        }
+
+        #[ink::test]
+        fn frozen_parent_accepts_no_new_subdomains() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            let add = |contract: &mut PeerName, subdomain: &[u8]| {
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    subdomain.to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                )
+            };
+            assert_eq!(add(&mut contract, b"pay"), Ok(()));
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.freeze_subdomains(b"alice".to_vec()),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert!(!contract.subdomains_frozen(b"alice".to_vec()));
+            assert_eq!(contract.freeze_subdomains(b"alice".to_vec()), Ok(()));
+            assert!(contract.subdomains_frozen(b"alice".to_vec()));
+            assert_eq!(add(&mut contract, b"mail"), Err(Error::SubdomainsFrozen));
+            // Existing subdomains are untouched.
+            assert!(contract.is_subdomain_exist(b"alice".to_vec(), b"pay".to_vec()));
+        }
+
+        #[ink::test]
+        fn burned_fuses_rule_out_their_actions() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.burn_fuses(b"alice".to_vec(), FUSE_CANNOT_TRANSFER),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.burn_fuses(b"alice".to_vec(), FUSE_CANNOT_TRANSFER), Ok(()));
+            assert_eq!(
+                contract.transfer_domain_ownership(b"alice".to_vec(), accounts.bob, false),
+                Err(Error::FuseBurned)
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.bob),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.bob,
+                    accounts.bob
+                ),
+                Ok(())
+            );
+
+            assert_eq!(
+                contract.burn_fuses(
+                    b"alice".to_vec(),
+                    FUSE_CANNOT_SET_RESOLVER | FUSE_CANNOT_CREATE_SUBDOMAIN
+                ),
+                Ok(())
+            );
+            // Burning only adds bits, earlier fuses stay burned.
+            assert_eq!(contract.burn_fuses(b"alice".to_vec(), 0), Ok(()));
+            assert_eq!(
+                contract.fuses(b"alice".to_vec()),
+                FUSE_CANNOT_TRANSFER | FUSE_CANNOT_SET_RESOLVER | FUSE_CANNOT_CREATE_SUBDOMAIN
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.alice),
+                Err(Error::FuseBurned)
+            );
+            assert_eq!(
+                contract.set_addr(b"alice".to_vec(), NATIVE_COIN_TYPE, vec![0x03; 32]),
+                Err(Error::FuseBurned)
+            );
+            assert_eq!(
+                contract.update_subdomain_resolver(
+                    b"alice".to_vec(),
+                    accounts.alice,
+                    b"pay".to_vec()
+                ),
+                Err(Error::FuseBurned)
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"mail".to_vec(),
+                    accounts.bob,
+                    accounts.bob
+                ),
+                Err(Error::FuseBurned)
+            );
+            assert_eq!(contract.domain_resolver(b"alice".to_vec()), Some(accounts.bob));
+            assert_eq!(
+                contract.subdomain_resolver(b"alice".to_vec(), b"pay".to_vec()),
+                Some(accounts.bob)
+            );
+        }
+
+        #[ink::test]
+        fn subdomain_freeze_ends_with_the_registration() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.freeze_subdomains(b"bob".to_vec()), Ok(()));
+
+            advance_past(1000 + GRACE_SECS * 1000);
+            set_caller(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.charlie, accounts.charlie, 1),
+                Ok(())
+            );
+            assert!(!contract.subdomains_frozen(b"bob".to_vec()));
+            set_caller(accounts.charlie);
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"bob".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.charlie,
+                    accounts.charlie
+                ),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn subdomains_are_indexed_by_parent() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            for subdomain in [b"pay".to_vec(), b"mail".to_vec()] {
+                assert_eq!(
+                    contract.set_sub_domain(
+                        b"alice".to_vec(),
+                        subdomain,
+                        accounts.alice,
+                        accounts.alice
+                    ),
+                    Ok(())
+                );
+            }
+            let pay = contract.get_subnode(b"alice".to_vec(), b"pay".to_vec());
+            let mail = contract.get_subnode(b"alice".to_vec(), b"mail".to_vec());
+            assert_eq!(contract.subdomains_of(b"alice".to_vec()), vec![pay, mail]);
+
+            assert_eq!(contract.revoke_subdomain(b"alice".to_vec(), b"pay".to_vec()), Ok(()));
+            assert_eq!(contract.subdomains_of(b"alice".to_vec()), vec![mail]);
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"mail".to_vec(),
+                    accounts.alice,
+                    accounts.alice
+                ),
+                Err(Error::NameAlreadyExists)
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.alice,
+                    accounts.alice
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.subdomains_of(b"alice".to_vec()), vec![mail, pay]);
+        }
+
+        #[ink::test]
+        fn name_counters_work() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.set_sub_domain(
+                    b"alice".to_vec(),
+                    b"pay".to_vec(),
+                    accounts.alice,
+                    accounts.alice
+                ),
+                Ok(())
+            );
+            assert_eq!(contract.total_names(), 2);
+            assert_eq!(contract.total_subdomains(), 1);
+            assert_eq!(contract.renounce_ownership(b"alice".to_vec()), Ok(()));
+            assert_eq!(
+                contract.renounce_ownership(b"alice".to_vec()),
+                Err(Error::NameNotExists)
+            );
+            assert_eq!(contract.total_names(), 1);
+        }
+
+        #[ink::test]
+        fn admin_can_export_all_nodes() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            for (domain, owner) in [
+                (b"alice".to_vec(), accounts.alice),
+                (b"bob".to_vec(), accounts.bob),
+                (b"eve".to_vec(), accounts.eve),
+            ] {
+                assert_eq!(register(&mut contract, domain, owner, owner, 1), Ok(()));
+            }
+            let alice = contract.compute_node(b"alice".to_vec());
+            let bob = contract.compute_node(b"bob".to_vec());
+            let eve = contract.compute_node(b"eve".to_vec());
+            assert_eq!(
+                contract.export_node(bob),
+                Ok(Some((accounts.bob, Some(accounts.bob))))
+            );
+            assert_eq!(
+                contract.export_nodes(1, 10),
+                Ok(vec![
+                    (bob, accounts.bob, Some(accounts.bob)),
+                    (eve, accounts.eve, Some(accounts.eve)),
+                ])
+            );
+
+            // The last node takes the index of a removed one.
+            assert_eq!(contract.renounce_ownership(b"alice".to_vec()), Ok(()));
+            assert_eq!(contract.export_node(alice), Ok(None));
+            assert_eq!(
+                contract.export_nodes(0, 10),
+                Ok(vec![
+                    (eve, accounts.eve, Some(accounts.eve)),
+                    (bob, accounts.bob, Some(accounts.bob)),
+                ])
+            );
+
+            set_caller(accounts.bob);
+            assert_eq!(contract.export_node(bob), Err(Error::UnauthorizedCaller));
+            assert_eq!(contract.export_nodes(0, 10), Err(Error::UnauthorizedCaller));
+        }
+
+        #[ink::test]
+        fn node_index_has_no_duplicates() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            let bob = contract.compute_node(b"bob".to_vec());
+            assert_eq!(contract.node_count(), 1);
+            assert_eq!(contract.node_at(0), Some(bob));
+
+            // Transfers and re-registration after a renounce keep a single entry.
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.transfer_domain_ownership(b"bob".to_vec(), accounts.eve, false),
+                Ok(accounts.bob)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.renounce_ownership(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.node_count(), 0);
+            assert_eq!(contract.node_at(0), None);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.node_count(), 1);
+            assert_eq!(contract.node_at(0), Some(bob));
+            assert_eq!(contract.node_at(1), None);
+        }
+
+        #[ink::test]
+        fn renounce_clears_records() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Ok(())
+            );
+            assert_eq!(contract.set_addr(b"bob".to_vec(), 60, vec![0xAB; 20]), Ok(()));
+            assert_eq!(contract.renounce_my_ownership(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), None);
+            assert_eq!(contract.get_text(b"bob".to_vec(), b"url".to_vec()), None);
+            assert_eq!(contract.addr(b"bob".to_vec(), 60), None);
+        }
+
+        #[ink::test]
+        fn pause_blocks_state_changes() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"alice".to_vec(), accounts.alice, accounts.alice, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.pause(), Err(Error::UnauthorizedCaller));
+            set_caller(accounts.alice);
+            assert_eq!(contract.pause(), Ok(()));
+            assert!(contract.paused());
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.bob),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                contract.set_approval_for_all(accounts.bob, true),
+                Err(Error::Paused)
+            );
+            // Queries keep working.
+            assert_eq!(contract.owner(b"alice".to_vec()), Some(accounts.alice));
+            assert_eq!(contract.unpause(), Ok(()));
+            assert_eq!(
+                contract.update_domain_resolver(b"alice".to_vec(), accounts.bob),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn availability_accounts_for_reservations_and_grace() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert!(contract.is_available(b"bob".to_vec()));
+            assert!(!contract.is_available(b"-bob".to_vec()));
+
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert!(!contract.is_available(b"bob".to_vec()));
+            advance_past(1000);
+            assert!(contract.is_in_grace_period(b"bob".to_vec()));
+            assert!(!contract.is_available(b"bob".to_vec()));
+            advance_past(1000 + GRACE_SECS * 1000);
+            assert!(contract.is_available(b"bob".to_vec()));
+
+            assert_eq!(contract.reserve_name(b"brand".to_vec(), true), Ok(()));
+            assert!(!contract.is_available(b"brand".to_vec()));
+        }
+
+        #[ink::test]
+        fn nothing_is_available_while_paused() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.pause(), Ok(()));
+            assert!(!contract.is_available(b"bob".to_vec()));
+            assert_eq!(contract.unpause(), Ok(()));
+            assert!(contract.is_available(b"bob".to_vec()));
+
+            let admins: Vec<(bool, AccountId)> = ink_env::test::recorded_events()
+                .filter_map(|event| match <Event as scale::Decode>::decode(&mut &event.data[..]) {
+                    Ok(Event::Paused(event)) => Some((true, event.admin)),
+                    Ok(Event::Unpaused(event)) => Some((false, event.admin)),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(admins, vec![(true, accounts.alice), (false, accounts.alice)]);
+        }
+
+        #[ink::test]
+        fn reserved_names_cannot_be_registered() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.reserve_name(b"brand".to_vec(), true), Ok(()));
+            assert!(contract.is_reserved(b"brand".to_vec()));
+            // Reserving twice does not list the name twice.
+            assert_eq!(contract.reserve_name(b"brand".to_vec(), true), Ok(()));
+            assert_eq!(
+                contract.reserved_names(),
+                vec![contract.compute_node(b"brand".to_vec())]
+            );
+            assert_eq!(
+                register(&mut contract, b"brand".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::NameReserved)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.reserve_name(b"brand".to_vec(), false),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.reserve_name(b"brand".to_vec(), false), Ok(()));
+            assert!(!contract.is_reserved(b"brand".to_vec()));
+            assert!(contract.reserved_names().is_empty());
+            // Releasing a name moves the last reservation into its place.
+            for name in [b"one".to_vec(), b"two".to_vec(), b"six".to_vec()] {
+                assert_eq!(contract.reserve_name(name, true), Ok(()));
+            }
+            assert_eq!(contract.reserve_name(b"one".to_vec(), false), Ok(()));
+            assert_eq!(
+                contract.reserved_names(),
+                vec![
+                    contract.compute_node(b"six".to_vec()),
+                    contract.compute_node(b"two".to_vec())
+                ]
+            );
+            assert_eq!(
+                register(&mut contract, b"brand".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn batch_register_works() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let domains = vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()];
+            assert_eq!(
+                contract.batch_register(domains, accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"two".to_vec()), Some(accounts.bob));
+            assert_eq!(contract.total_names(), 3);
+            let register_events = ink_env::test::recorded_events()
+                .filter(|event| {
+                    <Event as scale::Decode>::decode(&mut &event.data[..])
+                        .map(|event| matches!(event, Event::Register(_)))
+                        .unwrap_or(false)
+                })
+                .count();
+            assert_eq!(register_events, 3);
+        }
+
+        #[ink::test]
+        fn signed_registration_checks_nonce_and_signer() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            // A valid signature of an unrelated message, see `ink_env::ecdsa_recover`.
+            let signature: [u8; 65] = [
+                161, 234, 203, 74, 147, 96, 51, 212, 5, 174, 231, 9, 142, 48, 137, 201, 162, 118,
+                192, 67, 239, 16, 71, 216, 125, 86, 167, 139, 70, 7, 86, 241, 33, 87, 154, 251,
+                81, 29, 160, 4, 176, 239, 88, 211, 244, 232, 232, 52, 211, 234, 100, 115, 230, 47,
+                80, 44, 152, 166, 62, 50, 8, 13, 86, 175, 28,
+            ];
+            assert_eq!(contract.nonce_of(accounts.bob), 0);
+            set_caller(accounts.eve);
+            assert_eq!(
+                contract.register_domain_with_sig(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    0,
+                    signature
+                ),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(
+                contract.register_domain_with_sig(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    1,
+                    signature
+                ),
+                Err(Error::BadNonce)
+            );
+            assert_eq!(
+                contract.register_domain_with_sig(
+                    b"bob".to_vec(),
+                    accounts.bob,
+                    accounts.bob,
+                    1,
+                    0,
+                    signature
+                ),
+                Err(Error::InvalidSignature)
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), None);
+            assert_eq!(contract.nonce_of(accounts.bob), 0);
+            // The signed message is bound to the nonce.
+            assert_ne!(
+                contract.registration_hash(b"bob".to_vec(), accounts.bob, accounts.bob, 1, 0),
+                contract.registration_hash(b"bob".to_vec(), accounts.bob, accounts.bob, 1, 1)
+            );
+        }
+
+        #[ink::test]
+        fn signed_registration_registers_to_the_signer() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let secret_key = libsecp256k1::SecretKey::parse(&[0x11; 32]).expect("Invalid key");
+            let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+            let mut owner = <Blake2x256 as HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(&public_key.serialize_compressed(), &mut owner);
+            let owner = AccountId::from(owner);
+            let sign = |hash: [u8; 32]| {
+                let message = libsecp256k1::Message::parse(&hash);
+                let (signature, recovery_id) = libsecp256k1::sign(&message, &secret_key);
+                let mut output = [0u8; 65];
+                output[..64].copy_from_slice(&signature.serialize());
+                output[64] = recovery_id.serialize();
+                output
+            };
+
+            let signature = sign(contract.registration_hash(b"bob".to_vec(), owner, owner, 1, 0));
+            assert_eq!(
+                contract.register_domain_with_sig(b"bob".to_vec(), owner, owner, 1, 0, signature),
+                Ok(())
+            );
+            assert_eq!(contract.owner(b"bob".to_vec()), Some(owner));
+            assert_eq!(contract.nonce_of(owner), 1);
+            // The used nonce cannot be replayed, not even for another name.
+            let signature = sign(contract.registration_hash(b"eve".to_vec(), owner, owner, 1, 0));
+            assert_eq!(
+                contract.register_domain_with_sig(b"eve".to_vec(), owner, owner, 1, 0, signature),
+                Err(Error::BadNonce)
+            );
+        }
+
+        #[ink::test]
+        fn registrations_are_rate_limited() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_registration_limit(10, 2),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.alice);
+            assert_eq!(contract.set_registration_limit(10, 2), Ok(()));
+            assert_eq!(contract.registration_limit(), (10, 2));
+            assert_eq!(
+                contract.batch_register(vec![b"one".to_vec()], accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"two".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"three".to_vec(), accounts.bob, accounts.bob, 1),
+                Err(Error::RateLimited)
+            );
+            // Overwriting a live name counts against the limit too.
+            assert_eq!(
+                contract.register_domain(
+                    b"two".to_vec(),
+                    accounts.eve,
+                    accounts.eve,
+                    1,
+                    SECRET,
+                    true,
+                    None,
+                    Vec::new()
+                ),
+                Err(Error::RateLimited)
+            );
+            assert_eq!(
+                contract.batch_register(vec![b"three".to_vec()], accounts.bob, accounts.bob, 1),
+                Err(Error::RateLimited)
+            );
+
+            // A new window starts after `window_blocks` blocks.
+            for _ in 0..10 {
+                ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+                    .expect("Cannot advance block");
+            }
+            assert_eq!(
+                register(&mut contract, b"three".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+        }
+
+        #[ink::test]
+        fn batch_register_is_all_or_nothing() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"two".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            let domains = vec![b"one".to_vec(), b"two".to_vec()];
+            assert_eq!(
+                contract.batch_register(domains, accounts.charlie, accounts.charlie, 1),
+                Err(Error::NameAlreadyExists)
+            );
+            assert_eq!(contract.owner(b"one".to_vec()), None);
+            let domains = vec![b"one".to_vec(); MAX_BATCH_SIZE + 1];
+            assert_eq!(
+                contract.batch_register(domains, accounts.charlie, accounts.charlie, 1),
+                Err(Error::BatchTooLarge)
+            );
+        }
+
+        #[ink::test]
+        fn batch_transfer_is_all_or_nothing() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"one".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"two".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"three".to_vec(), accounts.charlie, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            let mixed = vec![b"one".to_vec(), b"three".to_vec()];
+            assert_eq!(
+                contract.batch_transfer(mixed, accounts.django),
+                Err(Error::UnauthorizedCaller)
+            );
+            assert_eq!(contract.owner(b"one".to_vec()), Some(accounts.bob));
+            let owned = vec![b"one".to_vec(), b"two".to_vec()];
+            assert_eq!(contract.batch_transfer(owned, accounts.django), Ok(()));
+            assert_eq!(contract.owner(b"one".to_vec()), Some(accounts.django));
+            assert_eq!(contract.owner(b"two".to_vec()), Some(accounts.django));
+            assert_eq!(contract.owner(b"three".to_vec()), Some(accounts.charlie));
+        }
+
+        #[ink::test]
+        fn default_resolver_replaces_zero_account() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_default_resolver(Some(accounts.charlie)), Ok(()));
+            assert_eq!(contract.default_resolver(), Some(accounts.charlie));
+            let zero = AccountId::from([0u8; 32]);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, zero, 1),
+                Ok(())
+            );
+            assert_eq!(
+                register(&mut contract, b"eve".to_vec(), accounts.eve, accounts.eve, 1),
+                Ok(())
+            );
+            assert_eq!(contract.domain_resolver(b"bob".to_vec()), Some(accounts.charlie));
+            assert_eq!(contract.domain_resolver(b"eve".to_vec()), Some(accounts.eve));
+
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_default_resolver(None),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn record_combines_owner_resolver_and_expiry() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.record(b"bob".to_vec()), None);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.record(b"bob".to_vec()),
+                Some((accounts.bob, Some(accounts.charlie), Some(1000), RECORD_KIND_WALLET))
+            );
+        }
+
+        #[ink::test]
+        fn record_kind_tags_the_resolver() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(contract.record_kind(b"bob".to_vec()), RECORD_KIND_WALLET);
+            assert_eq!(
+                contract.set_record_kind(b"bob".to_vec(), RECORD_KIND_CONTRACT),
+                Err(Error::UnauthorizedCaller)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_record_kind(b"bob".to_vec(), RECORD_KIND_IPV6 + 1),
+                Err(Error::InvalidRecordKind)
+            );
+            assert_eq!(contract.set_record_kind(b"bob".to_vec(), RECORD_KIND_IPFS), Ok(()));
+            assert_eq!(contract.record_kind(b"bob".to_vec()), RECORD_KIND_IPFS);
+            assert_eq!(
+                contract.record(b"bob".to_vec()),
+                Some((accounts.bob, Some(accounts.charlie), Some(1000), RECORD_KIND_IPFS))
+            );
+            assert_eq!(contract.clear_records(b"bob".to_vec()), Ok(()));
+            assert_eq!(contract.record_kind(b"bob".to_vec()), RECORD_KIND_WALLET);
+        }
+
+        #[ink::test]
+        fn domain_info_works() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.domain_info(b"bob".to_vec()), None);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(contract.set_lock(b"bob".to_vec(), true), Ok(()));
+            assert_eq!(
+                contract.domain_info(b"bob".to_vec()),
+                Some(DomainInfo {
+                    owner: accounts.bob,
+                    resolver: Some(accounts.charlie),
+                    expiry: Some(1000),
+                    label: Some(b"bob".to_vec()),
+                    locked: true,
+                })
+            );
+        }
+
+        #[ink::test]
+        fn records_present_reports_set_records() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.records_present(b"bob".to_vec()), RecordFlags::default());
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.bob, 1),
+                Ok(())
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), b"https://bob".to_vec()),
+                Ok(())
+            );
+            assert_eq!(
+                contract.records_present(b"bob".to_vec()),
+                RecordFlags {
+                    has_resolver: true,
+                    has_content_hash: false,
+                    has_any_text: true,
+                    has_any_addr: false,
+                }
+            );
+            assert_eq!(
+                contract.set_text(b"bob".to_vec(), b"url".to_vec(), Vec::new()),
+                Ok(())
+            );
+            assert!(!contract.records_present(b"bob".to_vec()).has_any_text);
+        }
+
+        #[ink::test]
+        fn resolvers_can_be_queried_in_bulk() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(
+                register(&mut contract, b"bob".to_vec(), accounts.bob, accounts.charlie, 1),
+                Ok(())
+            );
+            assert_eq!(
+                contract.resolvers_of(vec![b"bob".to_vec(), b"nobody".to_vec()]),
+                vec![Some(accounts.charlie), None]
+            );
+            let resolvers = contract.resolvers_of(vec![b"bob".to_vec(); MAX_BATCH_SIZE + 1]);
+            assert_eq!(resolvers, vec![Some(accounts.charlie); MAX_BATCH_SIZE]);
+        }
+
+        #[ink::test]
+        fn admin_can_withdraw() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let contract_id = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_id, 100)
+                .expect("Cannot set contract balance");
+            assert_eq!(
+                contract.withdraw(accounts.eve, 101),
+                Err(Error::InsufficientBalance)
+            );
+            assert_eq!(contract.withdraw(accounts.eve, 60), Ok(()));
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.eve),
+                Ok(60)
+            );
+            set_caller(accounts.bob);
+            assert_eq!(
+                contract.withdraw(accounts.bob, 40),
+                Err(Error::UnauthorizedCaller)
+            );
+        }
+
+        #[ink::test]
+        fn referrer_is_credited_on_registration() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            assert_eq!(contract.set_registration_fee(1000), Ok(()));
+            assert_eq!(contract.set_referral_bps(10_001), Err(Error::InvalidRate));
+            assert_eq!(contract.set_referral_bps(500), Ok(()));
+
+            for (domain, referrer) in [
+                (b"bob".to_vec(), Some(accounts.frank)),
+                (b"eve".to_vec(), Some(accounts.eve)),
+                (b"carol".to_vec(), None),
+            ] {
+                let commitment = contract.make_commitment(domain.clone(), accounts.eve, SECRET);
+                assert_eq!(contract.commit(commitment), Ok(()));
+                set_caller_with_value(accounts.alice, 1000);
+                assert_eq!(
+                    contract.register_domain(
+                        domain,
+                        accounts.eve,
+                        accounts.eve,
+                        1,
+                        SECRET,
+                        false,
+                        referrer,
+                        Vec::new()
+                    ),
+                    Ok(())
+                );
+            }
+            // Only the first registration had a referrer other than the owner.
+            assert_eq!(contract.balance_of(accounts.frank), 50);
+            assert_eq!(contract.balance_of(accounts.eve), 0);
+        }
+
+        #[ink::test]
+        fn credited_balance_can_be_pulled() {
+            let accounts = default_accounts();
+            let mut contract = new_contract(accounts.alice);
+            let contract_id = ink_env::account_id::<ink_env::DefaultEnvironment>();
+            ink_env::test::set_account_balance::<ink_env::DefaultEnvironment>(contract_id, 100)
+                .expect("Cannot set contract balance");
+            contract._credit(accounts.frank, 30).unwrap();
+            assert_eq!(contract.balance_of(accounts.frank), 30);
+            // Credited funds are not available to the admin.
+            assert_eq!(
+                contract.withdraw(accounts.eve, 71),
+                Err(Error::InsufficientBalance)
+            );
+
+            let before =
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.frank)
+                    .expect("Cannot get account balance");
+            set_caller(accounts.frank);
+            assert_eq!(contract.withdraw_balance(), Ok(()));
+            assert_eq!(contract.balance_of(accounts.frank), 0);
+            assert_eq!(
+                ink_env::test::get_account_balance::<ink_env::DefaultEnvironment>(accounts.frank),
+                Ok(before + 30)
+            );
+            assert_eq!(contract.withdraw_balance(), Err(Error::InsufficientBalance));
         }
     }
 }